@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines};
+
+// One entry of a batch file, consisting of all lines up to the next blank line.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Batch {
+    // line number of the first line, starting at 1
+    pub line: usize,
+    // trimmed, never empty
+    pub lines: Vec<String>,
+}
+
+impl Batch {
+    pub fn joined(&self) -> String {
+        self.lines.join(" ")
+    }
+}
+
+// Lazily splits the input into batches, so only a single entry is held in memory at a time.
+// Lines that are empty or whitespace only separate entries, CRLF line endings are accepted.
+pub struct Batches<R> {
+    lines: Lines<R>,
+    line_number: usize,
+}
+
+pub fn batches<R: BufRead>(reader: R) -> Batches<R> {
+    Batches {lines: reader.lines(), line_number: 0}
+}
+
+pub fn batches_from_file(filename: &str) -> io::Result<Batches<BufReader<File>>> {
+    let file = File::open(filename)?;
+    Ok(batches(BufReader::new(file)))
+}
+
+impl<R: BufRead> Iterator for Batches<R> {
+    type Item = io::Result<Batch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut batch: Option<Batch> = None;
+        for line in &mut self.lines {
+            self.line_number += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                if batch.is_some() {
+                    break;
+                }
+                continue;
+            }
+
+            let line_number = self.line_number;
+            batch.get_or_insert_with(|| Batch {line: line_number, lines: Vec::new()}).lines.push(String::from(line));
+        }
+
+        batch.map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use crate::{Batch, batches};
+    #[test]
+    fn split_batches() {
+        let input = "\n  \na b\r\nc \r\n \t\r\n\r\nd\n\n";
+        let batches = batches(input.as_bytes()).collect::<Result<Vec<Batch>, _>>().unwrap();
+        assert_eq!(batches, vec![
+            Batch {line: 3, lines: vec![String::from("a b"), String::from("c")]},
+            Batch {line: 7, lines: vec![String::from("d")]},
+        ]);
+        assert_eq!(batches[0].joined(), "a b c");
+    }
+
+    #[test]
+    fn no_trailing_newline() {
+        let batches = batches("x\n\ny".as_bytes()).map(|b| b.unwrap().line).collect::<Vec<usize>>();
+        assert_eq!(batches, vec![1, 3]);
+        assert_eq!(crate::batches("".as_bytes()).count(), 0);
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::collections::VecDeque;
 
 #[derive(Debug, PartialEq, Clone)]
 enum Square {
@@ -47,10 +48,10 @@ impl Hill {
         let mut squares = Vec::<Vec::<Square>>::new();
         for line in reader.lines() {
             let line_vec : Vec<Square> = line.unwrap().chars().map(|c| Square::from_char(&c).unwrap()).collect();
-            if squares.len() > 0 {
+            if !squares.is_empty() {
                 assert!(line_vec.len() == squares[0].len(), "Line length mismatch.");
             }
-            if line_vec.len() > 0 {
+            if !line_vec.is_empty() {
                 squares.push(line_vec);
             }
         }
@@ -91,6 +92,64 @@ impl Solution {
 
         Solution {hill, trees_hit}
     }
+
+    // Finds the route from the top left square to beyond the bottom row that hits the fewest trees.
+    // Each step may use any of the given (right, down) moves, right may be negative and wraps around
+    // like the hill itself. Returns None if the bottom cannot be reached with the given moves.
+    pub fn from_moves(mut hill: Hill, moves: &[(i32, u32)]) -> Option<Self> {
+        let height = hill.squares.len();
+        if height == 0 {
+            return None;
+        }
+        let width = hill.squares[0].len();
+        let is_tree = |square: &Square| *square == Square::Tree || *square == Square::TreeVisisted;
+
+        // moving onto a square costs either 0 or 1 trees, so a 0-1 BFS is enough
+        let mut trees = vec![None; height * width];
+        let mut previous: Vec<Option<usize>> = vec![None; height * width];
+        let mut queue = VecDeque::<(usize, usize)>::new();
+        let mut exit = None;
+        trees[0] = Some(0);
+        queue.push_back((0, 0));
+        while let Some((current, cost)) = queue.pop_front() {
+            if trees[current] != Some(cost) {
+                continue;
+            }
+            // entries come out with non-decreasing cost, so the first exit is the best one
+            if moves.iter().any(|&(_, down)| current / width + down as usize >= height) {
+                exit = Some((current, cost));
+                break;
+            }
+
+            for &(right, down) in moves {
+                let row = current / width + down as usize;
+                let column = (current % width) as i64 + right as i64;
+                let column = column.rem_euclid(width as i64) as usize;
+                let next = row * width + column;
+                let tree = is_tree(&hill.squares[row][column]);
+                let next_cost = cost + tree as usize;
+                if trees[next].is_none_or(|c| next_cost < c) {
+                    trees[next] = Some(next_cost);
+                    previous[next] = Some(current);
+                    if tree {
+                        queue.push_back((next, next_cost));
+                    } else {
+                        queue.push_front((next, next_cost));
+                    }
+                }
+            }
+        }
+
+        let (mut current, trees_hit) = exit?;
+        // the start square is not part of the route, same as for the fixed slopes
+        while let Some(before) = previous[current] {
+            let square = &mut hill.squares[current / width][current % width];
+            *square = if is_tree(square) { Square::TreeVisisted } else { Square::OpenVisisted };
+            current = before;
+        }
+
+        Some(Solution {hill, trees_hit})
+    }
 }
 
 fn main() {
@@ -109,6 +168,10 @@ fn main() {
     }
 
     println!("Part 2 res: {}", res);
+
+    let moves = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    let best = Solution::from_moves(hill, &moves).unwrap();
+    println!("Best route using any of the slopes per step hit {} trees.", best.trees_hit);
 }
 
 #[cfg(test)]
@@ -130,5 +193,24 @@ mod tests {
         assert_eq!(solution.trees_hit, 7);
     }
 
+    #[test]
+    fn shortest_path() {
+        let hill = Hill::from_file("testinput.txt");
+        let moves = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+        let best = Solution::from_moves(hill.clone(), &moves).unwrap();
+        for &(right, down) in moves.iter() {
+            let fixed = Solution::from_hill(hill.clone(), right as u32, down);
+            let single = Solution::from_moves(hill.clone(), &[(right, down)]).unwrap();
+            assert_eq!(single.trees_hit, fixed.trees_hit);
+            assert!(best.trees_hit <= fixed.trees_hit);
+        }
+        let marked = best.hill.squares.iter().flatten().filter(|&s| *s == Square::TreeVisisted).count();
+        assert_eq!(marked, best.trees_hit);
+
+        // straight down or sideways only
+        let best = Solution::from_moves(hill.clone(), &[(0, 1), (1, 0), (-1, 0)]).unwrap();
+        assert_eq!(best.trees_hit, 0);
+        assert!(Solution::from_moves(hill, &[(1, 0)]).is_none());
+    }
 
 }
\ No newline at end of file
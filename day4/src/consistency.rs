@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::Passport;
+use crate::fields::{BirthYear, ExpirationYear, IssueYear, PassportId};
+
+// A contradiction that only shows up when looking at a passport's fields together or at the whole batch.
+#[derive(Debug, PartialEq)]
+pub enum Conflict {
+    DuplicatePid { pid: PassportId, records: Vec<usize> },
+    ExpiresBeforeIssue { record: usize, iyr: IssueYear, eyr: ExpirationYear },
+    BornAfterIssue { record: usize, byr: BirthYear, iyr: IssueYear },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conflict::DuplicatePid { pid, records } => {
+                let records = records.iter().map(|r| r.to_string()).collect::<Vec<String>>();
+                write!(f, "Records {} share pid {}", records.join(", "), pid)
+            },
+            Conflict::ExpiresBeforeIssue { record, iyr, eyr } => write!(f, "Record {} expires in {} before being issued in {}", record, eyr, iyr),
+            Conflict::BornAfterIssue { record, byr, iyr } => write!(f, "Record {} was issued in {} before its holder was born in {}", record, iyr, byr),
+        }
+    }
+}
+
+// Record indices are positions within the batch. Missing fields never conflict.
+pub fn check_batch(passports: &[Passport]) -> Vec<Conflict> {
+    let mut conflicts = Vec::<Conflict>::new();
+    let mut pids = HashMap::<PassportId, Vec<usize>>::new();
+    for (record, passport) in passports.iter().enumerate() {
+        if let Some(pid) = passport.pid {
+            pids.entry(pid).or_default().push(record);
+        }
+        if let (Some(iyr), Some(eyr)) = (passport.iyr, passport.eyr) {
+            if eyr.0 < iyr.0 {
+                conflicts.push(Conflict::ExpiresBeforeIssue {record, iyr, eyr});
+            }
+        }
+        if let (Some(byr), Some(iyr)) = (passport.byr, passport.iyr) {
+            if byr.0 > iyr.0 {
+                conflicts.push(Conflict::BornAfterIssue {record, byr, iyr});
+            }
+        }
+    }
+
+    let mut duplicates = pids.into_iter().filter(|(_, records)| records.len() > 1).collect::<Vec<_>>();
+    duplicates.sort_by_key(|(_, records)| records[0]);
+    duplicates.into_iter()
+        .map(|(pid, records)| Conflict::DuplicatePid {pid, records})
+        .chain(conflicts)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::Passport;
+use crate::consistency::{Conflict, check_batch};
+use crate::fields::{BirthYear, ExpirationYear, IssueYear, PassportId};
+    #[test]
+    fn conflicts() {
+        let passports = [
+            "pid:000000001 byr:1990 iyr:2010 eyr:2020",
+            "pid:000000002 byr:2015 iyr:2012 eyr:2011",
+            "pid:000000001",
+            "pid:000000002 byr:1950",
+            "pid:000000001 iyr:2015",
+        ].iter().map(|p| Passport::from_str(p).unwrap()).collect::<Vec<Passport>>();
+        assert_eq!(check_batch(&passports), vec![
+            Conflict::DuplicatePid {pid: PassportId(1), records: vec![0, 2, 4]},
+            Conflict::DuplicatePid {pid: PassportId(2), records: vec![1, 3]},
+            Conflict::ExpiresBeforeIssue {record: 1, iyr: IssueYear(2012), eyr: ExpirationYear(2011)},
+            Conflict::BornAfterIssue {record: 1, byr: BirthYear(2015), iyr: IssueYear(2012)},
+        ]);
+        assert!(check_batch(&passports[..1]).is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Serializer};
+
+// Parses a plain unsigned decimal, unlike u32::from_str this rejects signs and empty input.
+pub fn parse_digits(value: &str) -> Result<u32, String> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("{} is not a number.", value));
+    }
+    u32::from_str(value).map_err(|_| format!("{} is too large.", value))
+}
+
+// Serializes a field in the same canonical text form as used in the batch files.
+macro_rules! serialize_as_str {
+    ($name:ident) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+    };
+}
+
+// Years are exactly four digits, their sane ranges are up to the schema.
+macro_rules! year {
+    ($name:ident) => {
+        #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Serialize)]
+        pub struct $name(pub u32);
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(entry: &str) -> Result<Self, Self::Err> {
+                if entry.len() != 4 {
+                    return Err(format!("Year {} does not have 4 digits.", entry));
+                }
+
+                Ok($name(parse_digits(entry)?))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+
+year!(BirthYear);
+year!(IssueYear);
+year!(ExpirationYear);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HeightUnit {
+    Cm,
+    In,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Height {
+    pub value: u32,
+    pub unit: HeightUnit,
+}
+
+impl FromStr for Height {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let (value, unit) = if let Some(value) = entry.strip_suffix("cm") {
+            (value, HeightUnit::Cm)
+        } else if let Some(value) = entry.strip_suffix("in") {
+            (value, HeightUnit::In)
+        } else {
+            return Err(format!("Missing unit in height {}.", entry));
+        };
+        // leading zeros are dropped, so 0170cm becomes 170cm
+        let value = parse_digits(value)?;
+
+        Ok(Height {value, unit})
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match self.unit {
+            HeightUnit::Cm => "cm",
+            HeightUnit::In => "in",
+        };
+        write!(f, "{}{}", self.value, unit)
+    }
+}
+
+serialize_as_str!(Height);
+
+// a # followed by exactly six lowercase hex digits
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HexColor(pub u32);
+
+impl FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let digits = entry.strip_prefix('#').ok_or(format!("Missing # in color {}.", entry))?;
+        if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+            return Err(format!("Invalid hex digits in color {}.", entry));
+        }
+        let color = u32::from_str_radix(digits, 16).map_err(|e| e.to_string())?;
+
+        Ok(HexColor(color))
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:06x}", self.0)
+    }
+}
+
+serialize_as_str!(HexColor);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EyeColor {
+    Amb,
+    Blu,
+    Brn,
+    Gry,
+    Grn,
+    Hzl,
+    Oth,
+}
+
+impl FromStr for EyeColor {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        match entry {
+            "amb" => Ok(EyeColor::Amb),
+            "blu" => Ok(EyeColor::Blu),
+            "brn" => Ok(EyeColor::Brn),
+            "gry" => Ok(EyeColor::Gry),
+            "grn" => Ok(EyeColor::Grn),
+            "hzl" => Ok(EyeColor::Hzl),
+            "oth" => Ok(EyeColor::Oth),
+            _ => Err(format!("Unknown eye color {}.", entry)),
+        }
+    }
+}
+
+impl fmt::Display for EyeColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let res = match self {
+            EyeColor::Amb => "amb",
+            EyeColor::Blu => "blu",
+            EyeColor::Brn => "brn",
+            EyeColor::Gry => "gry",
+            EyeColor::Grn => "grn",
+            EyeColor::Hzl => "hzl",
+            EyeColor::Oth => "oth",
+        };
+        write!(f, "{}", res)
+    }
+}
+
+serialize_as_str!(EyeColor);
+
+// nine digits, leading zeros are significant
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct PassportId(pub u32);
+
+impl FromStr for PassportId {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        if entry.len() != 9 {
+            return Err(format!("Passport id {} does not have 9 digits.", entry));
+        }
+
+        Ok(PassportId(parse_digits(entry)?))
+    }
+}
+
+impl fmt::Display for PassportId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:09}", self.0)
+    }
+}
+
+serialize_as_str!(PassportId);
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct CountryId(pub String);
+
+impl FromStr for CountryId {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        if entry.is_empty() {
+            return Err(String::from("Empty country id."));
+        }
+
+        Ok(CountryId(String::from(entry)))
+    }
+}
+
+impl fmt::Display for CountryId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::fields::{BirthYear, EyeColor, Height, HeightUnit, HexColor, PassportId};
+    #[test]
+    fn heights() {
+        assert_eq!(Height::from_str("179cm"), Ok(Height {value: 179, unit: HeightUnit::Cm}));
+        assert_eq!(Height::from_str("60in").unwrap().to_string(), "60in");
+        assert_eq!(Height::from_str("1790cm"), Ok(Height {value: 1790, unit: HeightUnit::Cm}));
+        assert_eq!(Height::from_str("0170cm").unwrap().to_string(), "170cm");
+        for bad in ["", "cm", "in", "170", "-170cm", "+170cm", "17 0cm", "170 cm", "99999999999cm", "1cmin", "ünin"].iter() {
+            assert!(Height::from_str(bad).is_err(), "{} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(HexColor::from_str("#123abc"), Ok(HexColor(0x123abc)));
+        assert_eq!(HexColor::from_str("#00000f").unwrap().to_string(), "#00000f");
+        for bad in ["", "#", "123abc", "#12345g", "#123ab", "#123abcd", "#ABCDEF", "#ABCdef", "#+12345", "#12ü45"].iter() {
+            assert!(HexColor::from_str(bad).is_err(), "{} accepted", bad);
+        }
+        assert_eq!(EyeColor::from_str("hzl"), Ok(EyeColor::Hzl));
+        assert_eq!(EyeColor::Gry.to_string(), "gry");
+        assert!(EyeColor::from_str("BRN").is_err());
+        assert!(EyeColor::from_str("zzz").is_err());
+        assert!(EyeColor::from_str("").is_err());
+    }
+
+    #[test]
+    fn passport_ids() {
+        assert_eq!(PassportId::from_str("000000001"), Ok(PassportId(1)));
+        assert_eq!(PassportId(87499704).to_string(), "087499704");
+        for bad in ["", "12345678", "0123456789", "+12345678", "12345678a", "1234567٣"].iter() {
+            assert!(PassportId::from_str(bad).is_err(), "{} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn years() {
+        assert_eq!(BirthYear::from_str("1920"), Ok(BirthYear(1920)));
+        for bad in ["", "192", "19200", "+192", "19a0"].iter() {
+            assert!(BirthYear::from_str(bad).is_err(), "{} accepted", bad);
+        }
+    }
+}
@@ -1,172 +1,254 @@
 use std::str::FromStr;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::env;
+use batch_reader::batches_from_file;
+use consistency::check_batch;
+use lazy_static::lazy_static;
+use serde::{Serialize, Serializer};
+use fields::{BirthYear, CountryId, ExpirationYear, EyeColor, Height, HexColor, IssueYear, PassportId};
+use record::Record;
+use repair::{Confidence, repaired, suggest};
+use schema::Schema;
+use validation::{BatchReport, Validation};
 
-#[derive(Debug, PartialEq, Default)]
+mod consistency;
+mod fields;
+mod record;
+mod repair;
+mod schema;
+mod validation;
+
+lazy_static! {
+    static ref PASSPORT_SCHEMA: Schema = Schema::from_str(include_str!("../passport.toml")).unwrap();
+}
+
+// Values that are present but cannot be parsed are kept in malformed, so they still count as present.
+// When a field is given more than once the first value is used and the others are kept in duplicates,
+// so the validation can reject them.
+#[derive(Debug, PartialEq, Default, Serialize)]
 struct Passport {
-    byr: String,
-    iyr: String,
-    eyr: String,
-    hgt: String,
-    hcl: String,
-    ecl: String,
-    pid: String,
-    cid: String,
+    byr: Option<BirthYear>,
+    iyr: Option<IssueYear>,
+    eyr: Option<ExpirationYear>,
+    hgt: Option<Height>,
+    hcl: Option<HexColor>,
+    ecl: Option<EyeColor>,
+    pid: Option<PassportId>,
+    cid: Option<CountryId>,
+    #[serde(serialize_with = "serialize_fields")]
+    malformed: Vec<(String, String)>,
+    #[serde(serialize_with = "serialize_fields")]
+    duplicates: Vec<(String, String)>,
+}
+
+// Raw fields are exported in the batch file syntax, so they fit into a single CSV column.
+fn serialize_fields<S: Serializer>(fields: &[(String, String)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&Record {fields: fields.to_vec()})
 }
 
 impl FromStr for Passport {
     type Err = String;
 
     fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let record = Record::from_str(entry)?;
+        Passport::from_record(&record)
+    }
+
+}
+
+impl Passport {
+    fn from_record(record: &Record) -> Result<Self, String> {
+        PASSPORT_SCHEMA.check_keys(record)?;
         let mut ret = Passport::default();
-        let parts: Vec<&str> = entry.trim().split(' ').collect();
-        for part in parts.iter() {
-            let key = &part[0..3];
-            let val = &part[4..];
-            match key {
-                "byr" => ret.byr = String::from(val),
-                "iyr" => ret.iyr = String::from(val),
-                "eyr" => ret.eyr = String::from(val),
-                "hgt" => ret.hgt = String::from(val),
-                "hcl" => ret.hcl = String::from(val),
-                "ecl" => ret.ecl = String::from(val),
-                "pid" => ret.pid = String::from(val),
-                "cid" => ret.cid = String::from(val),
+        for (i, (key, val)) in record.fields.iter().enumerate() {
+            if record.fields[..i].iter().any(|(k, _)| k == key) {
+                ret.duplicates.push((key.clone(), val.clone()));
+                continue;
+            }
+            let parsed = match key.as_str() {
+                "byr" => BirthYear::from_str(val).map(|v| ret.byr = Some(v)),
+                "iyr" => IssueYear::from_str(val).map(|v| ret.iyr = Some(v)),
+                "eyr" => ExpirationYear::from_str(val).map(|v| ret.eyr = Some(v)),
+                "hgt" => Height::from_str(val).map(|v| ret.hgt = Some(v)),
+                "hcl" => HexColor::from_str(val).map(|v| ret.hcl = Some(v)),
+                "ecl" => EyeColor::from_str(val).map(|v| ret.ecl = Some(v)),
+                "pid" => PassportId::from_str(val).map(|v| ret.pid = Some(v)),
+                "cid" => CountryId::from_str(val).map(|v| ret.cid = Some(v)),
                 _ => return Err(format!("Unexpected value {}.", key)),
+            };
+            if parsed.is_err() {
+                ret.malformed.push((key.clone(), val.clone()));
             }
         }
 
         Ok(ret)
     }
 
-}
+    // the normalized values, followed by the malformed and repeated ones as they were given
+    fn to_record(&self) -> Record {
+        let fields = [
+            ("byr", self.byr.map(|v| v.to_string())),
+            ("iyr", self.iyr.map(|v| v.to_string())),
+            ("eyr", self.eyr.map(|v| v.to_string())),
+            ("hgt", self.hgt.map(|v| v.to_string())),
+            ("hcl", self.hcl.map(|v| v.to_string())),
+            ("ecl", self.ecl.map(|v| v.to_string())),
+            ("pid", self.pid.map(|v| v.to_string())),
+            ("cid", self.cid.as_ref().map(|v| v.to_string())),
+        ];
+        let fields = fields.iter()
+            .filter_map(|(key, val)| val.as_ref().map(|v| (String::from(*key), v.clone())))
+            .chain(self.malformed.iter().cloned())
+            .chain(self.duplicates.iter().cloned())
+            .collect();
+        Record {fields}
+    }
 
-impl Passport {
     fn is_valid(&self) -> bool {
-        self.byr.len() > 0
-        && self.iyr.len() > 0
-        && self.eyr.len() > 0
-        && self.hgt.len() > 0
-        && self.hcl.len() > 0
-        && self.ecl.len() > 0
-        && self.pid.len() > 0
-        // && self.cid.len() > 0  // ignored for task 1
+        PASSPORT_SCHEMA.is_complete(&self.to_record())
+    }
+
+    fn validate(&self) -> Validation {
+        PASSPORT_SCHEMA.validate(&self.to_record())
     }
 
+    // the sane ranges are up to the schema, but the typed values must parse regardless of its config
     fn is_valid_and_sane(&self) -> bool {
-        if !self.is_valid() {
-            return false;
-        }
+        self.validate().is_valid() && self.malformed.is_empty()
+    }
+}
 
-        let byr = u32::from_str(&self.byr).unwrap_or(0);
-        if byr < 1920 || byr > 2002 {
-            return false;
-        }
+fn passports_to_csv(passports: &[Passport]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::<u8>::new());
+    for passport in passports.iter() {
+        writer.serialize(passport).unwrap();
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
 
-        let iyr = u32::from_str(&self.iyr).unwrap_or(0);
-        if iyr < 2010 || iyr > 2020 {
-            return false;
-        }
+// streams the records of a batch file together with the line they start at
+fn records_from_file(filename: &str) -> impl Iterator<Item = (usize, Record)> {
+    batches_from_file(filename).unwrap().map(|batch| {
+        let batch = batch.unwrap();
+        let record = Record::from_str(&batch.joined()).unwrap_or_else(|e| panic!("Line {}: {}", batch.line, e));
+        (batch.line, record)
+    })
+}
 
-        let eyr = u32::from_str(&self.eyr).unwrap_or(0);
-        if eyr < 2020 || eyr > 2030 {
-            return false;
-        }
+fn passports(filename: &str) -> impl Iterator<Item = Passport> {
+    records_from_file(filename).map(|(line, record)| {
+        Passport::from_record(&record).unwrap_or_else(|e| panic!("Line {}: {}", line, e))
+    })
+}
 
-        let unit = &self.hgt[self.hgt.len() - 2..];
-        if unit == "cm" {
-            let hgt = u32::from_str(&self.hgt[0..3]).unwrap_or(0);
-            if hgt < 150 || hgt > 193 {
-                return false;
-            }
-        } else if unit == "in" {
-            let hgt = u32::from_str(&self.hgt[0..2]).unwrap_or(0);
-            if hgt < 59 || hgt > 76 {
-                return false;
-            }
-        } else {
-            return false;
-        }
+fn passports_from_file(filename: &str) -> Vec<Passport> {
+    passports(filename).collect()
+}
 
-        if &self.hcl[0..1] != "#" {
-            return false;
-        }
-        let hcl = u32::from_str_radix(&self.hcl[1..], 16);
-        if hcl.is_err() {
-            return false;
-        }
+fn main() {
+    // usage: day4 [--report|--json|--conflicts|--repair|--fix|--export-json|--export-csv] [input] [schema]
+    // --repair lists suggested fixes, --fix prints the batch with all fixes of at least medium confidence applied
+    // without a schema the input is checked as passports
+    let args: Vec<String> = env::args().skip(1).collect();
+    let report = args.iter().any(|a| a == "--report");
+    let json = args.iter().any(|a| a == "--json");
+    let conflicts = args.iter().any(|a| a == "--conflicts");
+    let repair = args.iter().any(|a| a == "--repair");
+    let fix = args.iter().any(|a| a == "--fix");
+    let export_json = args.iter().any(|a| a == "--export-json");
+    let export_csv = args.iter().any(|a| a == "--export-csv");
+    let args: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    let input = args.first().map_or("input.txt", |s| s.as_str());
+    let custom_schema = args.get(1).map(|f| Schema::from_file(f).unwrap());
+    let schema = custom_schema.as_ref().unwrap_or(&PASSPORT_SCHEMA);
 
-        let valid_ecls = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
-        if !valid_ecls.iter().any(|x| x == &self.ecl) {
-            return false;
+    if report || json {
+        let report = BatchReport::from_validations(records_from_file(input).map(|(_, r)| schema.validate(&r)));
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        } else {
+            print!("{}", report);
         }
+        return;
+    }
 
-        if self.pid.len() != 9 || u32::from_str(&self.pid).is_err() {
-            return false;
+    if conflicts {
+        let passports = passports_from_file(input);
+        let conflicts = check_batch(&passports);
+        for conflict in conflicts.iter() {
+            println!("{}", conflict);
         }
-
-        true
+        println!("{} conflicts in {} records.", conflicts.len(), passports.len());
+        return;
     }
-}
 
-fn passports_from_file(filename: &str) -> Vec<Passport> {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-    let mut passports = Vec::<Passport>::new();
-    let mut current_passport_data = String::new();
-    for line in reader.lines() {
-        let line = line.unwrap();
-        if line.len() == 0 {
-            // end of passport entry, generate new passport
-            if current_passport_data.len() > 0 {
-                let passport = Passport::from_str(&current_passport_data).unwrap();
-                passports.push(passport);
-                current_passport_data = String::new();
+    if repair {
+        for (record, passport) in passports(input).enumerate() {
+            for repair in suggest(&passport) {
+                println!("Record {}: {}", record, repair);
             }
-
-            continue;
         }
-
-        current_passport_data += line.trim();
-        current_passport_data += " ";
+        return;
     }
 
-    if current_passport_data.len() > 0 {
-        let passport = Passport::from_str(&current_passport_data).unwrap();
-        passports.push(passport);
+    if fix {
+        let records = passports(input).map(|p| repaired(&p, Confidence::Medium).to_string()).collect::<Vec<String>>();
+        println!("{}", records.join("\n\n"));
+        return;
     }
 
-    passports
-}
+    if export_json || export_csv {
+        let passports = passports_from_file(input);
+        if export_json {
+            println!("{}", serde_json::to_string_pretty(&passports).unwrap());
+        } else {
+            print!("{}", passports_to_csv(&passports));
+        }
+        return;
+    }
 
-fn main() {
     println!("Hello, world!");
-    let passports = passports_from_file("input.txt");
-    let valid_count = passports.iter().filter(|p| p.is_valid()).count();
-    println!("Valid for part 1: {}", valid_count);
+    if custom_schema.is_some() {
+        let (mut count, mut complete_count, mut valid_count) = (0, 0, 0);
+        for (_, record) in records_from_file(input) {
+            count += 1;
+            complete_count += schema.is_complete(&record) as usize;
+            valid_count += schema.is_valid(&record) as usize;
+        }
+        println!("{} records of type {}, {} complete, {} valid.", count, schema.name, complete_count, valid_count);
+        return;
+    }
 
-    let valid_count = passports.iter().filter(|p| p.is_valid_and_sane()).count();
-    println!("Valid for part 2: {}", valid_count);
+    // single pass, so arbitrarily large batches never have to be held in memory
+    let (mut valid_count, mut sane_count) = (0, 0);
+    for passport in passports(input) {
+        valid_count += passport.is_valid() as usize;
+        sane_count += passport.is_valid_and_sane() as usize;
+    }
+    println!("Valid for part 1: {}", valid_count);
+    println!("Valid for part 2: {}", sane_count);
 }
 
 
 #[cfg(test)]
 mod tests {
-use crate::Passport;
-use crate::passports_from_file;
+use std::str::FromStr;
+use crate::{Passport, passports_from_file, passports_to_csv};
+use crate::fields::{BirthYear, ExpirationYear, EyeColor, Height, HeightUnit, HexColor, IssueYear, PassportId};
     #[test]
     fn single_passport() {
         let passports = passports_from_file("singlepassport.txt");
         assert_eq!(passports.len(), 1);
         assert_eq!(passports[0], Passport {
-            hcl: String::from("#ae17e1"),
-            iyr: String::from("2013"),
-            eyr: String::from("2024"),
-            ecl: String::from("brn"),
-            pid: String::from("760753108"),
-            byr: String::from("1931"),
-            hgt: String::from("179cm"),
-            cid: String::from(""),
+            hcl: Some(HexColor(0xae17e1)),
+            iyr: Some(IssueYear(2013)),
+            eyr: Some(ExpirationYear(2024)),
+            ecl: Some(EyeColor::Brn),
+            pid: Some(PassportId(760753108)),
+            byr: Some(BirthYear(1931)),
+            hgt: Some(Height {value: 179, unit: HeightUnit::Cm}),
+            cid: None,
+            malformed: vec![],
+            duplicates: vec![],
         })
     }
 
@@ -184,6 +266,8 @@ use crate::passports_from_file;
         assert_eq!(passports.len(), 4);
         let valid_count = passports.iter().filter(|p| p.is_valid_and_sane()).count();
         assert_eq!(valid_count, 0);
+        assert!(passports.iter().all(|p| !p.validate().errors.is_empty()));
+        assert_eq!(passports[0].validate().errors.len(), 3);
     }
 
     #[test]
@@ -194,5 +278,58 @@ use crate::passports_from_file;
         assert_eq!(valid_count, 4);
     }
 
+    #[test]
+    fn malformed_passports() {
+        assert!(Passport::from_str("byr:1920 xyz:12").is_err());
+        assert!(Passport::from_str("byr1920").is_err());
+        assert!(Passport::from_str("ab c").is_err());
+        // the unit does not truncate the number anymore
+        let passport = Passport::from_str("byr:1980 iyr:2012 eyr:2030 hgt:1750cm hcl:#623a2f ecl:grn pid:087499704").unwrap();
+        assert!(passport.is_valid());
+        assert!(!passport.is_valid_and_sane());
+        assert!(passport.hgt.is_some());
+        assert!(passport.ecl.is_some());
+
+        // none of these may panic
+        for entry in ["hgt:", "hgt:c hcl:", "hcl:# ecl:", "pid: byr:", "hgt:ü hcl:#ü", ":", "byr::"].iter() {
+            if let Ok(passport) = Passport::from_str(entry) {
+                assert!(!passport.is_valid_and_sane());
+                assert!(passport.hgt.is_none());
+                assert!(passport.hcl.is_none());
+                assert!(passport.pid.is_none());
+            }
+        }
+
+        // a repeated field only rejects its own record
+        let passport = Passport::from_str("pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f byr:1981").unwrap();
+        assert_eq!(passport.byr, Some(BirthYear(1980)));
+        assert!(passport.is_valid());
+        assert!(!passport.is_valid_and_sane());
+        assert_eq!(passport.validate().errors[0].problem.reason(), "duplicate");
+
+        // the schema only allows lowercase colors, so the typed values must not accept others either
+        let passport = Passport::from_str("pid:087499704 hgt:74in ecl:BRN iyr:2012 eyr:2030 byr:1980 hcl:#ABCDEF").unwrap();
+        assert!(passport.is_valid());
+        assert!(!passport.is_valid_and_sane());
+        assert!(passport.hcl.is_none() && passport.ecl.is_none());
+    }
+
+    #[test]
+    fn normalized_export() {
+        let passport = Passport::from_str("hgt:0170cm ecl:brn hcl:#abcdef pid:012533040 byr:1946 iyr:2010 cid:12 eyr:20").unwrap();
+        assert_eq!(passport.malformed, vec![(String::from("eyr"), String::from("20"))]);
+        assert!(passport.is_valid());
+        let json = serde_json::to_value(&passport).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "byr": 1946, "iyr": 2010, "eyr": null, "hgt": "170cm", "hcl": "#abcdef", "ecl": "brn", "pid": "012533040", "cid": "12",
+            "malformed": "eyr:20", "duplicates": "",
+        }));
+
+        let csv = passports_to_csv(&[passport, Passport::from_str("hgt:x pid:1 hgt:y").unwrap()]);
+        assert_eq!(csv, "byr,iyr,eyr,hgt,hcl,ecl,pid,cid,malformed,duplicates\n\
+            1946,2010,,170cm,#abcdef,brn,012533040,12,eyr:20,\n\
+            ,,,,,,,,hgt:x pid:1,hgt:y\n");
+    }
+
 
 }
@@ -0,0 +1,39 @@
+use std::fmt;
+use std::str::FromStr;
+
+// A single batch entry as raw key:value pairs, in the order they appeared.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Record {
+    pub fields: Vec<(String, String)>,
+}
+
+impl Record {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+impl FromStr for Record {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::<(String, String)>::new();
+        for part in entry.split_whitespace() {
+            let (key, val) = part.split_once(':').ok_or(format!("Missing ':' in {}.", part))?;
+            if key.is_empty() {
+                return Err(format!("Missing key in {}.", part));
+            }
+            fields.push((String::from(key), String::from(val)));
+        }
+
+        Ok(Record {fields})
+    }
+}
+
+// writes the record back in the batch file format, on a single line
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = self.fields.iter().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<String>>();
+        write!(f, "{}", parts.join(" "))
+    }
+}
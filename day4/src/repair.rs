@@ -0,0 +1,109 @@
+use std::fmt;
+use crate::{PASSPORT_SCHEMA, Passport};
+use crate::record::Record;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+// A proposed replacement for a single malformed value.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Repair {
+    pub key: String,
+    pub from: String,
+    pub to: String,
+    pub confidence: Confidence,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {} ({}, {:?} confidence)", self.key, self.from, self.to, self.reason, self.confidence)
+    }
+}
+
+fn suggest_value(key: &str, value: &str) -> Option<(String, Confidence, &'static str)> {
+    let digits_only = !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit());
+    match key {
+        "hgt" if digits_only => {
+            // the sane ranges of the schema do not overlap, so the magnitude gives away the unit
+            let value = value.parse::<u32>().ok()?;
+            ["cm", "in"].iter()
+                .map(|unit| format!("{}{}", value, unit))
+                .find(|h| PASSPORT_SCHEMA.accepts("hgt", h))
+                .map(|h| (h, Confidence::High, "unit inferred from magnitude"))
+        },
+        "hcl" if value.len() == 6 && value.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Some((format!("#{}", value.to_ascii_lowercase()), Confidence::High, "missing #"))
+        },
+        "pid" if digits_only && value.len() == 8 => {
+            Some((format!("{:0>9}", value), Confidence::Medium, "dropped leading zero"))
+        },
+        "pid" if digits_only && value.len() < 8 => {
+            Some((format!("{:0>9}", value), Confidence::Low, "dropped leading zeros"))
+        },
+        _ => None,
+    }
+}
+
+// Only passports failing the sanity checks get suggestions, and only for values that could not be parsed.
+pub fn suggest(passport: &Passport) -> Vec<Repair> {
+    if passport.is_valid_and_sane() {
+        return Vec::new();
+    }
+
+    passport.malformed.iter().filter_map(|(key, value)| {
+        suggest_value(key, value).map(|(to, confidence, reason)| Repair {
+            key: key.clone(),
+            from: value.clone(),
+            to,
+            confidence,
+            reason,
+        })
+    }).collect()
+}
+
+// Applies all suggestions of at least the given confidence and returns the corrected record.
+pub fn repaired(passport: &Passport, min_confidence: Confidence) -> Record {
+    let repairs = suggest(passport);
+    let mut record = passport.to_record();
+    for (key, value) in record.fields.iter_mut() {
+        if let Some(repair) = repairs.iter().find(|r| &r.key == key && &r.from == value && r.confidence >= min_confidence) {
+            *value = repair.to.clone();
+        }
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::Passport;
+use crate::repair::{Confidence, repaired, suggest};
+    #[test]
+    fn suggestions() {
+        let passport = Passport::from_str("hgt:170 hcl:dab227 pid:12533040 ecl:grn byr:1946 iyr:2019 eyr:2020").unwrap();
+        let repairs = suggest(&passport);
+        let repairs = repairs.iter().map(|r| (r.key.as_str(), r.to.as_str(), r.confidence)).collect::<Vec<_>>();
+        assert_eq!(repairs, vec![
+            ("hgt", "170cm", Confidence::High),
+            ("hcl", "#dab227", Confidence::High),
+            ("pid", "012533040", Confidence::Medium),
+        ]);
+
+        let fixed = Passport::from_record(&repaired(&passport, Confidence::High)).unwrap();
+        assert!(!fixed.is_valid_and_sane());
+        let fixed = Passport::from_record(&repaired(&passport, Confidence::Medium)).unwrap();
+        assert!(fixed.is_valid_and_sane());
+        assert_eq!(fixed.to_record().to_string(), "byr:1946 iyr:2019 eyr:2020 hgt:170cm hcl:#dab227 ecl:grn pid:012533040");
+
+        // nothing to guess for these
+        let passport = Passport::from_str("hgt:100 hcl:xyz123 pid:1234567890").unwrap();
+        assert!(suggest(&passport).is_empty());
+        let passport = Passport::from_str("hgt:60 pid:33").unwrap();
+        assert_eq!(suggest(&passport).iter().map(|r| r.confidence).collect::<Vec<_>>(), vec![Confidence::High, Confidence::Low]);
+    }
+}
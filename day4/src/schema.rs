@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::str::FromStr;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use crate::fields::parse_digits;
+use crate::record::Record;
+use crate::validation::{FieldError, Problem, Validation};
+
+// Describes a document type: which keys exist, which are required and how their values are checked.
+#[derive(Debug, Deserialize)]
+pub struct Schema {
+    pub name: String,
+    pub fields: Vec<FieldSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldSpec {
+    pub key: String,
+    #[serde(default = "FieldSpec::default_required")]
+    pub required: bool,
+    #[serde(default)]
+    pub validators: Vec<Validator>,
+}
+
+impl FieldSpec {
+    fn default_required() -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Validator {
+    IntRange { min: i64, max: i64 },
+    // the value is a number directly followed by one of the units, each unit has its own range
+    UnitRange { units: BTreeMap<String, (i64, i64)> },
+    Regex { pattern: Pattern },
+    Enum { values: Vec<String> },
+    Length { min: usize, max: usize },
+}
+
+// a regex compiled once while loading the schema
+#[derive(Debug)]
+pub struct Pattern(Regex);
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map(Pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Validator {
+    pub fn check(&self, value: &str) -> Result<(), Problem> {
+        match self {
+            Validator::IntRange { min, max } => {
+                let number = parse_digits(value).map_err(|_| Problem::NotANumber {value: String::from(value)})?;
+                check_range(number.into(), *min, *max)
+            },
+            Validator::UnitRange { units } => {
+                let (number, (min, max)) = units.iter()
+                    .find_map(|(unit, range)| value.strip_suffix(unit.as_str()).map(|v| (v, range)))
+                    .ok_or_else(|| Problem::BadUnit {value: String::from(value), units: units.keys().cloned().collect()})?;
+                let number = parse_digits(number).map_err(|_| Problem::NotANumber {value: String::from(value)})?;
+                check_range(number.into(), *min, *max)
+            },
+            Validator::Regex { pattern } => match pattern.0.is_match(value) {
+                true => Ok(()),
+                false => Err(Problem::PatternMismatch {value: String::from(value), pattern: String::from(pattern.0.as_str())}),
+            },
+            Validator::Enum { values } => match values.iter().any(|v| v == value) {
+                true => Ok(()),
+                false => Err(Problem::NotAllowed {value: String::from(value), allowed: values.clone()}),
+            },
+            Validator::Length { min, max } => match (*min..=*max).contains(&value.chars().count()) {
+                true => Ok(()),
+                false => Err(Problem::BadLength {value: String::from(value), min: *min, max: *max}),
+            },
+        }
+    }
+}
+
+fn check_range(value: i64, min: i64, max: i64) -> Result<(), Problem> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(Problem::OutOfRange {value, min, max})
+    }
+}
+
+impl FromStr for Schema {
+    type Err = String;
+
+    fn from_str(toml_str: &str) -> Result<Self, Self::Err> {
+        let schema: Schema = toml::from_str(toml_str).map_err(|e| format!("Invalid schema: {}.", e))?;
+        for (i, field) in schema.fields.iter().enumerate() {
+            if schema.fields[..i].iter().any(|f| f.key == field.key) {
+                return Err(format!("Field {} is defined twice.", field.key));
+            }
+        }
+        Ok(schema)
+    }
+}
+
+impl Schema {
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let toml_str = fs::read_to_string(filename).map_err(|e| format!("Cannot read {}: {}.", filename, e))?;
+        Schema::from_str(&toml_str)
+    }
+
+    pub fn field(&self, key: &str) -> Option<&FieldSpec> {
+        self.fields.iter().find(|f| f.key == key)
+    }
+
+    // whether a single value would pass all validators of its field
+    pub fn accepts(&self, key: &str, value: &str) -> bool {
+        self.field(key).is_some_and(|f| f.validators.iter().all(|v| v.check(value).is_ok()))
+    }
+
+    // rejects records containing keys this schema does not know about
+    pub fn check_keys(&self, record: &Record) -> Result<(), String> {
+        match record.fields.iter().find(|(key, _)| self.field(key).is_none()) {
+            Some((key, _)) => Err(format!("Unexpected value {}.", key)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_complete(&self, record: &Record) -> bool {
+        self.fields.iter()
+            .filter(|f| f.required)
+            .all(|f| record.get(&f.key).is_some_and(|v| !v.is_empty()))
+    }
+
+    // checks every field, reporting the first failing validator of each, a field given more than once
+    // is rejected as a whole
+    pub fn validate(&self, record: &Record) -> Validation {
+        let mut errors = Vec::<FieldError>::new();
+        for (key, value) in record.fields.iter() {
+            if self.field(key).is_none() {
+                errors.push(FieldError {key: key.clone(), problem: Problem::Unexpected {value: value.clone()}});
+            }
+        }
+        for field in self.fields.iter() {
+            let values = record.fields.iter().filter(|(k, _)| k == &field.key).map(|(_, v)| v.clone()).collect::<Vec<String>>();
+            let problem = match record.get(&field.key) {
+                _ if values.len() > 1 => Some(Problem::Duplicate {values}),
+                None | Some("") if field.required => Some(Problem::Missing),
+                None => None,
+                Some(value) => field.validators.iter().find_map(|v| v.check(value).err()),
+            };
+            if let Some(problem) = problem {
+                errors.push(FieldError {key: field.key.clone(), problem});
+            }
+        }
+        Validation {errors}
+    }
+
+    pub fn is_valid(&self, record: &Record) -> bool {
+        self.validate(record).is_valid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::record::Record;
+use crate::schema::Schema;
+use crate::validation::Problem;
+    #[test]
+    fn custom_schema() {
+        let schema = Schema::from_str(r#"
+            name = "ticket"
+            [[fields]]
+            key = "seat"
+            validators = [{ type = "regex", pattern = "^[0-9]+[A-F]$" }, { type = "length", min = 2, max = 3 }]
+            [[fields]]
+            key = "weight"
+            required = false
+            validators = [{ type = "unit_range", units = { kg = [1, 30], lb = [2, 66] } }]
+        "#).unwrap();
+        assert_eq!(schema.name, "ticket");
+        assert!(schema.is_valid(&Record::from_str("seat:12C weight:20kg").unwrap()));
+        assert!(schema.is_valid(&Record::from_str("seat:1A").unwrap()));
+        assert!(!schema.is_valid(&Record::from_str("seat:1234C").unwrap()));
+        assert!(!schema.is_valid(&Record::from_str("seat:12C weight:31kg").unwrap()));
+        assert!(!schema.is_valid(&Record::from_str("weight:20lb").unwrap()));
+        assert!(schema.check_keys(&Record::from_str("seat:1A gate:4").unwrap()).is_err());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let schema = Schema::from_str(include_str!("../passport.toml")).unwrap();
+        let record = Record::from_str("byr:1900 iyr:abcd eyr:2020 hgt:180 hcl:123abc ecl:xyz pid:12 foo:bar").unwrap();
+        let validation = schema.validate(&record);
+        let problems = validation.errors.iter().map(|e| (e.key.as_str(), e.problem.reason())).collect::<Vec<_>>();
+        assert_eq!(problems, vec![
+            ("foo", "unexpected"),
+            ("byr", "out_of_range"),
+            ("iyr", "pattern_mismatch"),
+            ("hgt", "bad_unit"),
+            ("hcl", "pattern_mismatch"),
+            ("ecl", "not_allowed"),
+            ("pid", "bad_length"),
+        ]);
+        assert_eq!(validation.errors[1].problem, Problem::OutOfRange {value: 1900, min: 1920, max: 2002});
+
+        let validation = schema.validate(&Record::from_str("hgt:190in").unwrap());
+        assert_eq!(validation.errors.iter().filter(|e| e.problem == Problem::Missing).count(), 6);
+        assert_eq!(validation.errors[3].problem, Problem::OutOfRange {value: 190, min: 59, max: 76});
+        assert!(schema.accepts("hgt", "60in"));
+        assert!(!schema.accepts("hgt", "60cm"));
+        assert!(!schema.accepts("foo", "bar"));
+
+        let validation = schema.validate(&Record::from_str("byr:1950 byr:1900").unwrap());
+        let duplicate = Problem::Duplicate {values: vec![String::from("1950"), String::from("1900")]};
+        assert_eq!(validation.errors[0].problem, duplicate);
+        assert_eq!(duplicate.to_string(), "given 2 times: 1950, 1900");
+    }
+
+    #[test]
+    fn signs() {
+        // numbers are plain digits, the same as for the typed fields
+        let schema = Schema::from_str(r#"
+            name = "signs"
+            [[fields]]
+            key = "hgt"
+            validators = [{ type = "unit_range", units = { cm = [150, 193] } }]
+            [[fields]]
+            key = "n"
+            validators = [{ type = "int_range", min = -10, max = 10 }]
+        "#).unwrap();
+        let validation = schema.validate(&Record::from_str("hgt:+170cm n:-5").unwrap());
+        assert_eq!(validation.errors[0].problem, Problem::NotANumber {value: String::from("+170cm")});
+        assert_eq!(validation.errors[1].problem, Problem::NotANumber {value: String::from("-5")});
+        assert!(schema.is_valid(&Record::from_str("hgt:170cm n:5").unwrap()));
+    }
+
+    #[test]
+    fn invalid_schema() {
+        assert!(Schema::from_str("name = \"x\"\n[[fields]]\nkey = \"a\"\nvalidators = [{ type = \"regex\", pattern = \"(\" }]").is_err());
+        assert!(Schema::from_str("name = \"x\"\n[[fields]]\nkey = \"a\"\n[[fields]]\nkey = \"a\"").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::Serialize;
+
+// Why a single field was rejected.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Problem {
+    Missing,
+    Unexpected { value: String },
+    Duplicate { values: Vec<String> },
+    NotANumber { value: String },
+    OutOfRange { value: i64, min: i64, max: i64 },
+    BadUnit { value: String, units: Vec<String> },
+    PatternMismatch { value: String, pattern: String },
+    NotAllowed { value: String, allowed: Vec<String> },
+    BadLength { value: String, min: usize, max: usize },
+}
+
+impl Problem {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Problem::Missing => "missing",
+            Problem::Unexpected { .. } => "unexpected",
+            Problem::Duplicate { .. } => "duplicate",
+            Problem::NotANumber { .. } => "not_a_number",
+            Problem::OutOfRange { .. } => "out_of_range",
+            Problem::BadUnit { .. } => "bad_unit",
+            Problem::PatternMismatch { .. } => "pattern_mismatch",
+            Problem::NotAllowed { .. } => "not_allowed",
+            Problem::BadLength { .. } => "bad_length",
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Missing => write!(f, "missing"),
+            Problem::Unexpected { value } => write!(f, "unexpected field with value {}", value),
+            Problem::Duplicate { values } => write!(f, "given {} times: {}", values.len(), values.join(", ")),
+            Problem::NotANumber { value } => write!(f, "{} is not a number", value),
+            Problem::OutOfRange { value, min, max } => write!(f, "{} is not within {}..={}", value, min, max),
+            Problem::BadUnit { value, units } => write!(f, "{} does not end in one of {}", value, units.join(", ")),
+            Problem::PatternMismatch { value, pattern } => write!(f, "{} does not match {}", value, pattern),
+            Problem::NotAllowed { value, allowed } => write!(f, "{} is not one of {}", value, allowed.join(", ")),
+            Problem::BadLength { value, min, max } => write!(f, "{} is not {}..={} characters long", value, min, max),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FieldError {
+    pub key: String,
+    #[serde(flatten)]
+    pub problem: Problem,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.problem)
+    }
+}
+
+// All problems found in one record, empty if the record is valid.
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Validation {
+    pub errors: Vec<FieldError>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RecordReport {
+    pub index: usize,
+    pub errors: Vec<FieldError>,
+}
+
+// Aggregated results of a whole batch, only rejected records are listed individually.
+#[derive(Debug, PartialEq, Default, Serialize)]
+pub struct BatchReport {
+    pub records: usize,
+    pub valid: usize,
+    // field -> reason -> number of records
+    pub failures: BTreeMap<String, BTreeMap<String, usize>>,
+    pub rejected: Vec<RecordReport>,
+}
+
+impl BatchReport {
+    pub fn from_validations<I: IntoIterator<Item = Validation>>(validations: I) -> Self {
+        let mut report = BatchReport::default();
+        for (index, validation) in validations.into_iter().enumerate() {
+            report.records += 1;
+            if validation.is_valid() {
+                report.valid += 1;
+                continue;
+            }
+            for error in validation.errors.iter() {
+                let reasons = report.failures.entry(error.key.clone()).or_default();
+                *reasons.entry(String::from(error.problem.reason())).or_default() += 1;
+            }
+            report.rejected.push(RecordReport {index, errors: validation.errors});
+        }
+        report
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for record in self.rejected.iter() {
+            let errors = record.errors.iter().map(|e| e.to_string()).collect::<Vec<String>>();
+            writeln!(f, "Record {}: {}", record.index, errors.join("; "))?;
+        }
+        writeln!(f, "{} of {} records valid.", self.valid, self.records)?;
+        for (key, reasons) in self.failures.iter() {
+            for (reason, count) in reasons.iter() {
+                writeln!(f, "{} {}: {}", key, reason, count)?;
+            }
+        }
+        Ok(())
+    }
+}
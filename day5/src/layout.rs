@@ -0,0 +1,232 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use crate::Seat;
+
+// Number of characters needed to address count rows or columns.
+fn code_len(count: u32) -> usize {
+    (32 - (count - 1).leading_zeros()) as usize
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PassError {
+    Length { expected: usize, found: usize },
+    // position counts characters from the start of the pass
+    InvalidChar { position: usize, found: char },
+    OutsidePlane { row: u32, column: u32 },
+}
+
+impl fmt::Display for PassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PassError::Length { expected, found } => write!(f, "Expected {} characters, found {}.", expected, found),
+            PassError::InvalidChar { position, found } => write!(f, "Invalid character {} at position {}.", found, position),
+            PassError::OutsidePlane { row, column } => write!(f, "Row {}, column {} is outside of the plane.", row, column),
+        }
+    }
+}
+
+// Describes an aircraft: its size and which symbols select the lower and upper half.
+// Each half of a pass is a binary number, so a plane may have fewer rows than its codes can address.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlaneLayout {
+    pub rows: u32,
+    pub columns: u32,
+    pub row_symbols: (char, char),
+    pub column_symbols: (char, char),
+}
+
+impl Default for PlaneLayout {
+    fn default() -> Self {
+        PlaneLayout {rows: 128, columns: 8, row_symbols: ('F', 'B'), column_symbols: ('L', 'R')}
+    }
+}
+
+impl PlaneLayout {
+    pub fn row_code_len(&self) -> usize {
+        code_len(self.rows)
+    }
+
+    pub fn column_code_len(&self) -> usize {
+        code_len(self.columns)
+    }
+
+    pub fn code_len(&self) -> usize {
+        self.row_code_len() + self.column_code_len()
+    }
+
+    pub fn contains(&self, seat: &Seat) -> bool {
+        seat.row < self.rows && seat.column < self.columns
+    }
+
+    pub fn get_id(&self, seat: &Seat) -> u32 {
+        seat.row * self.columns + seat.column
+    }
+
+    pub fn seat(&self, id: u32) -> Option<Seat> {
+        let seat = Seat {row: id / self.columns, column: id % self.columns};
+        if self.contains(&seat) { Some(seat) } else { None }
+    }
+
+    // every seat of the plane, ordered by id
+    pub fn seats(&self) -> impl Iterator<Item = Seat> {
+        let columns = self.columns;
+        (0..self.rows).flat_map(move |row| (0..columns).map(move |column| Seat {row, column}))
+    }
+
+    pub fn empty_seats(&self, taken: &BTreeSet<Seat>) -> BTreeSet<Seat> {
+        self.seats().collect::<BTreeSet<Seat>>().difference(taken).cloned().collect()
+    }
+
+    // Each character is one bit of the row or column, the upper symbol being a 1.
+    pub fn decode(&self, pass: &str) -> Result<Seat, PassError> {
+        let row_len = self.row_code_len();
+        let (mut row, mut column, mut len) = (0, 0, 0);
+        for (position, c) in pass.chars().enumerate() {
+            let (current, (lower, upper)) = match position < row_len {
+                true => (&mut row, self.row_symbols),
+                false => (&mut column, self.column_symbols),
+            };
+            let bit = match c {
+                c if c == lower => 0,
+                c if c == upper => 1,
+                found => return Err(PassError::InvalidChar {position, found}),
+            };
+            // overlong passes are reported below, this only keeps the shift from overflowing
+            if position < self.code_len() {
+                *current = *current << 1 | bit;
+            }
+            len += 1;
+        }
+        if len != self.code_len() {
+            return Err(PassError::Length {expected: self.code_len(), found: len});
+        }
+        let seat = Seat {row, column};
+        if !self.contains(&seat) {
+            return Err(PassError::OutsidePlane {row, column});
+        }
+
+        Ok(seat)
+    }
+
+    // decodes one pass per line, skipping blank lines, together with the line number starting at 1
+    pub fn decode_batch<'a>(&'a self, input: &'a str) -> impl Iterator<Item = (usize, &'a str, Result<Seat, PassError>)> + 'a {
+        input.lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty())
+            .map(move |(i, l)| (i, l, self.decode(l)))
+    }
+
+    pub fn encode(&self, seat: &Seat) -> Result<String, String> {
+        if !self.contains(seat) {
+            return Err(format!("Seat {:?} is outside of the plane.", seat));
+        }
+        let encode_half = |value: u32, len: usize, (lower, upper): (char, char)| {
+            (0..len).rev().map(move |bit| if value >> bit & 1 == 1 { upper } else { lower })
+        };
+
+        Ok(encode_half(seat.row, self.row_code_len(), self.row_symbols)
+            .chain(encode_half(seat.column, self.column_code_len(), self.column_symbols))
+            .collect())
+    }
+}
+
+// Written as ROWSxCOLUMNS:ROW_SYMBOLS:COLUMN_SYMBOLS, the default plane is 128x8:FB:LR.
+impl FromStr for PlaneLayout {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let parts = entry.split(':').collect::<Vec<&str>>();
+        let (size, row_symbols, column_symbols) = match parts[..] {
+            [size, row_symbols, column_symbols] => (size, row_symbols, column_symbols),
+            _ => return Err(format!("Invalid layout {}.", entry)),
+        };
+        let (rows, columns) = size.split_once('x').ok_or(format!("Invalid size in layout {}.", entry))?;
+        let rows = u32::from_str(rows).map_err(|e| e.to_string())?;
+        let columns = u32::from_str(columns).map_err(|e| e.to_string())?;
+        if rows == 0 || columns == 0 || rows > 1 << 16 || columns > 1 << 16 {
+            return Err(format!("Unsupported plane size in layout {}.", entry));
+        }
+        let symbols = |s: &str| match s.chars().collect::<Vec<char>>()[..] {
+            [lower, upper] if lower != upper => Ok((lower, upper)),
+            _ => Err(format!("Invalid symbols {} in layout {}.", s, entry)),
+        };
+
+        Ok(PlaneLayout {rows, columns, row_symbols: symbols(row_symbols)?, column_symbols: symbols(column_symbols)?})
+    }
+}
+
+impl fmt::Display for PlaneLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}:{}{}:{}{}", self.rows, self.columns, self.row_symbols.0, self.row_symbols.1, self.column_symbols.0, self.column_symbols.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::{Seat, binary_search};
+use crate::layout::{PassError, PlaneLayout};
+    #[test]
+    fn default_layout() {
+        let layout = PlaneLayout::default();
+        assert_eq!(layout.code_len(), 10);
+        assert_eq!(PlaneLayout::from_str("128x8:FB:LR"), Ok(layout.clone()));
+        let seat = layout.decode("FBFBBFFRLR").unwrap();
+        assert_eq!(seat, Seat {row: 44, column: 5});
+        assert_eq!(layout.get_id(&seat), 357);
+        assert_eq!(layout.encode(&seat).unwrap(), "FBFBBFFRLR");
+        assert_eq!(layout.decode("FBFBBFFRL"), Err(PassError::Length {expected: 10, found: 9}));
+        assert_eq!(layout.decode("FBFBBFFRLRR"), Err(PassError::Length {expected: 10, found: 11}));
+        assert_eq!(layout.decode("FBFBBFFRLX"), Err(PassError::InvalidChar {position: 9, found: 'X'}));
+        assert_eq!(layout.decode("FBFBBFFFLR"), Err(PassError::InvalidChar {position: 7, found: 'F'}));
+        assert_eq!(layout.decode("FBFBBFFRLRÜ"), Err(PassError::InvalidChar {position: 10, found: 'Ü'}));
+    }
+
+    #[test]
+    fn same_as_binary_search() {
+        let layout = PlaneLayout::default();
+        for id in 0..1024 {
+            let seat = Seat {row: id / 8, column: id % 8};
+            let pass = layout.encode(&seat).unwrap();
+            let row = binary_search((0, 127), 'F', 'B', &pass[0..7]);
+            let column = binary_search((0, 7), 'L', 'R', &pass[7..10]);
+            assert_eq!(layout.decode(&pass), Ok(Seat {row, column}));
+        }
+
+        let input = "FBFBBFFRLR\n\nBFFFBBFRRR\r\nBFFFBBFRRX\n";
+        let seats = layout.decode_batch(input).collect::<Vec<_>>();
+        assert_eq!(seats, vec![
+            (1, "FBFBBFFRLR", Ok(Seat {row: 44, column: 5})),
+            (3, "BFFFBBFRRR", Ok(Seat {row: 70, column: 7})),
+            (4, "BFFFBBFRRX", Err(PassError::InvalidChar {position: 9, found: 'X'})),
+        ]);
+    }
+
+    #[test]
+    fn custom_layout() {
+        let layout = PlaneLayout::from_str("30x6:AZ:<>").unwrap();
+        assert_eq!((layout.row_code_len(), layout.column_code_len()), (5, 3));
+        assert_eq!(layout.to_string(), "30x6:AZ:<>");
+        for row in 0..30 {
+            for column in 0..6 {
+                let seat = Seat {row, column};
+                let pass = layout.encode(&seat).unwrap();
+                assert_eq!(layout.decode(&pass), Ok(Seat {row, column}));
+                assert_eq!(layout.get_id(&seat), row * 6 + column);
+            }
+        }
+        assert!(layout.encode(&Seat {row: 30, column: 0}).is_err());
+        assert_eq!(layout.decode("ZZZZZ<<<"), Err(PassError::OutsidePlane {row: 31, column: 0}));
+        assert!(layout.decode("AAAAA>>>").is_err());
+
+        let layout = PlaneLayout::from_str("1x1:AB:CD").unwrap();
+        assert_eq!(layout.encode(&Seat {row: 0, column: 0}), Ok(String::new()));
+        assert_eq!(layout.decode(""), Ok(Seat {row: 0, column: 0}));
+
+        for bad in ["", "0x8:FB:LR", "128x8:FF:LR", "128x8:FB:L", "128:FB:LR", "128x8:FB"].iter() {
+            assert!(PlaneLayout::from_str(bad).is_err(), "{} accepted", bad);
+        }
+    }
+}
@@ -1,32 +1,23 @@
 use std::str::FromStr;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::cmp::Ordering;
-
-#[derive(Debug, Eq)]
+use std::fs;
+use std::env;
+use layout::PlaneLayout;
+use manifest::Manifest;
+use seatmap::SeatMap;
+
+mod layout;
+mod manifest;
+mod seatmap;
+
+// Ordering by row first and then by column matches the order of the ids on every plane.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
 struct Seat {
     row: u32,
     column: u32,
 }
 
-impl Ord for Seat {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.get_id().cmp(&other.get_id())
-    }
-}
-
-impl PartialOrd for Seat {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.get_id().cmp(&other.get_id()))
-    }
-}
-
-impl PartialEq for Seat {
-    fn eq(&self, other: &Self) -> bool {
-        self == other
-    }
-}
-
+// the original bisection, kept as reference for the bitwise decoding in PlaneLayout
+#[cfg(test)]
 fn binary_search(bounds: (u32, u32), lower_symbol: char, upper_symbol: char, spec: &str) -> u32 {
     let mut current_bounds = bounds;
     
@@ -56,61 +47,73 @@ impl FromStr for Seat {
     type Err = String;
 
     fn from_str(entry: &str) -> Result<Self, Self::Err> {
-        if entry.len() != 10 {
-            return Err(format!("Invalid seat specification {}.", entry));
-        };
-
-        let row = &entry[0..7];
-        let column = &entry[7..10];
-        let row = binary_search((0, 127), 'F', 'B', row);
-        let column = binary_search((0, 7), 'L', 'R', column);
-        
-        Ok(Seat {row, column})
+        PlaneLayout::default().decode(entry).map_err(|e| format!("Invalid seat specification {}: {}", entry, e))
     }
 
 }
 
-impl Seat {
-    pub fn get_id(&self) -> u32 {
-        self.row * 8 + self.column
+fn main() {
+    // usage: day5 [--map|--svg|--missing|--occupancy] [input] [layout], see PlaneLayout for the layout format
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().any(|a| a == name);
+    let (map, svg, missing, occupancy) = (flag("--map"), flag("--svg"), flag("--missing"), flag("--occupancy"));
+    let args: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    let input = args.first().map_or("input.txt", |s| s.as_str());
+    let layout = args.get(1).map_or(PlaneLayout::default(), |l| PlaneLayout::from_str(l).unwrap());
+    let manifest = Manifest::check(&layout, &fs::read_to_string(input).unwrap());
+    // the answers below only use the valid passes, so flag everything else loudly
+    for problem in manifest.problems.iter() {
+        eprintln!("{}", problem);
     }
-}
+    if !manifest.problems.is_empty() {
+        eprintln!("{} problems in manifest {}.", manifest.problems.len(), input);
+    }
+    if manifest.seats.is_empty() {
+        eprintln!("No valid boarding passes in manifest {}.", input);
+        std::process::exit(1);
+    }
+    let seats = manifest.seats;
 
-fn seats_from_file(filename: &str) -> Vec<Seat> {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-    // first determine dimensions lines x linelength
-    let mut seats = Vec::<Seat>::new();
-    for line in reader.lines() {
-        let line = line.unwrap();
-        if line.len() < 1 {
-            continue;
+    let seat_map = SeatMap::new(&layout, &seats);
+    if map {
+        print!("{}", seat_map.to_ascii());
+    }
+    if svg {
+        print!("{}", seat_map.to_svg());
+    }
+    if missing {
+        for seat in seat_map.missing() {
+            println!("Missing seat {} (row {}, column {}, pass {})", layout.get_id(&seat), seat.row, seat.column, layout.encode(&seat).unwrap());
         }
-        let seat = Seat::from_str(&line).unwrap();
-        seats.push(seat);
+    }
+    if occupancy {
+        for (row, count) in seat_map.row_occupancy().iter().enumerate() {
+            println!("Row {}: {}/{}", row, count, layout.columns);
+        }
+        for (column, count) in seat_map.column_occupancy().iter().enumerate() {
+            println!("Column {}: {}/{}", column, count, layout.rows);
+        }
+    }
+    if map || svg || missing || occupancy {
+        return;
     }
 
-    seats
-}
-
-fn main() {
-    let mut seats = seats_from_file("input.txt");
-    seats.sort();
-    let max = &seats.last().unwrap();
-    println!("Max for 1: {}", max.get_id());
-    let min = &seats.first().unwrap();
-    let mut last = min.get_id();
-    for seat in seats {
-        if seat.get_id() - last > 1 {
-            println!("Your seat is {}", seat.get_id() - 1);
+    let max = seats.iter().next_back().unwrap();
+    println!("Max for 1: {}", layout.get_id(max));
+    for seat in layout.empty_seats(&seats) {
+        let id = layout.get_id(&seat);
+        let taken = |id: Option<u32>| id.and_then(|id| layout.seat(id)).is_some_and(|s| seats.contains(&s));
+        if taken(id.checked_sub(1)) && taken(id.checked_add(1)) {
+            println!("Your seat is {} with boarding pass {}", id, layout.encode(&seat).unwrap());
         }
-        last = seat.get_id();
     }
 }
 
 #[cfg(test)]
 mod tests {
 use crate::Seat;
+use crate::layout::PlaneLayout;
+use std::collections::{BTreeSet, HashSet};
 use std::str::FromStr;
     #[test]
     fn single_seats() {
@@ -119,7 +122,28 @@ use std::str::FromStr;
             let seat = Seat::from_str(seat_str).unwrap();
             assert_eq!(seat.row, row);
             assert_eq!(seat.column, column);
-            assert_eq!(seat.get_id(), id);
+            assert_eq!(PlaneLayout::default().get_id(&seat), id);
         }
     }
+
+    #[test]
+    fn seat_sets() {
+        let passes = ["FBFBBFFRLR", "BFFFBBFRRR", "FBFBBFFRLR", "FFFBBBFRRR", "BBFFBBFRLL"];
+        let seats = passes.iter().map(|p| Seat::from_str(p).unwrap()).collect::<Vec<Seat>>();
+        assert_eq!(seats[0], seats[2]);
+        assert_ne!(seats[0], seats[1]);
+        assert_eq!(seats.iter().collect::<HashSet<&Seat>>().len(), 4);
+        let ordered = seats.iter().cloned().collect::<BTreeSet<Seat>>();
+        let layout = PlaneLayout::default();
+        let ids = ordered.iter().map(|s| layout.get_id(s)).collect::<Vec<u32>>();
+        assert_eq!(ids, vec![119, 357, 567, 820]);
+
+        assert_eq!(layout.seats().count(), 1024);
+        let empty = layout.empty_seats(&ordered);
+        assert_eq!(empty.len(), 1020);
+        assert!(empty.is_disjoint(&ordered));
+        assert_eq!(empty.union(&ordered).count(), 1024);
+        assert_eq!(layout.seat(357), Some(seats[0]));
+        assert_eq!(layout.seat(1024), None);
+    }
 }
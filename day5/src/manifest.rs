@@ -0,0 +1,70 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use crate::Seat;
+use crate::layout::{PassError, PlaneLayout};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ManifestProblem {
+    // also covers passes that decode to a seat outside the plane
+    Invalid { line: usize, pass: String, error: PassError },
+    DoubleBooked { line: usize, pass: String, first_line: usize },
+}
+
+impl fmt::Display for ManifestProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManifestProblem::Invalid { line, pass, error } => write!(f, "Line {}: pass {} is invalid: {}", line, pass, error),
+            ManifestProblem::DoubleBooked { line, pass, first_line } => write!(f, "Line {}: pass {} was already used on line {}", line, pass, first_line),
+        }
+    }
+}
+
+// The seats of a manifest, every seat only once, and everything wrong with the passes.
+#[derive(Debug, PartialEq, Default)]
+pub struct Manifest {
+    pub seats: BTreeSet<Seat>,
+    pub problems: Vec<ManifestProblem>,
+}
+
+impl Manifest {
+    pub fn check(layout: &PlaneLayout, input: &str) -> Self {
+        let mut manifest = Manifest::default();
+        let mut first_lines = BTreeMap::<Seat, usize>::new();
+        for (line, pass, seat) in layout.decode_batch(input) {
+            let pass_string = String::from(pass);
+            match seat {
+                Ok(seat) => match first_lines.get(&seat) {
+                    Some(&first_line) => manifest.problems.push(ManifestProblem::DoubleBooked {line, pass: pass_string, first_line}),
+                    None => {
+                        first_lines.insert(seat, line);
+                        manifest.seats.insert(seat);
+                    },
+                },
+                Err(error) => manifest.problems.push(ManifestProblem::Invalid {line, pass: pass_string, error}),
+            }
+        }
+        manifest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use crate::Seat;
+use crate::layout::{PassError, PlaneLayout};
+use crate::manifest::{Manifest, ManifestProblem};
+    #[test]
+    fn problems() {
+        let layout = "100x8:FB:LR".parse::<PlaneLayout>().unwrap();
+        let input = "FBFBBFFRLR\nBFFFBBFRRR\n\nFBFBBFFRLR\nBBBBBBBRRR\nFBFBBFFRLX\nFBFBBFFRLR\n";
+        let manifest = Manifest::check(&layout, input);
+        assert_eq!(manifest.seats.len(), 2);
+        assert!(manifest.seats.contains(&Seat {row: 44, column: 5}));
+        assert_eq!(manifest.problems, vec![
+            ManifestProblem::DoubleBooked {line: 4, pass: String::from("FBFBBFFRLR"), first_line: 1},
+            ManifestProblem::Invalid {line: 5, pass: String::from("BBBBBBBRRR"), error: PassError::OutsidePlane {row: 127, column: 7}},
+            ManifestProblem::Invalid {line: 6, pass: String::from("FBFBBFFRLX"), error: PassError::InvalidChar {position: 9, found: 'X'}},
+            ManifestProblem::DoubleBooked {line: 7, pass: String::from("FBFBBFFRLR"), first_line: 1},
+        ]);
+        assert_eq!(manifest.problems[0].to_string(), "Line 4: pass FBFBBFFRLR was already used on line 1");
+    }
+}
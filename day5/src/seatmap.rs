@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+use crate::Seat;
+use crate::layout::PlaneLayout;
+
+// Occupancy of a whole plane, built from the seats found in a manifest.
+pub struct SeatMap<'a> {
+    layout: &'a PlaneLayout,
+    taken: &'a BTreeSet<Seat>,
+}
+
+impl<'a> SeatMap<'a> {
+    const SVG_CELL: u32 = 12;
+
+    pub fn new(layout: &'a PlaneLayout, taken: &'a BTreeSet<Seat>) -> Self {
+        SeatMap {layout, taken}
+    }
+
+    pub fn missing(&self) -> BTreeSet<Seat> {
+        self.layout.empty_seats(self.taken)
+    }
+
+    // number of taken seats per row, indexed by row
+    pub fn row_occupancy(&self) -> Vec<u32> {
+        let mut rows = vec![0; self.layout.rows as usize];
+        for seat in self.taken.iter().filter(|s| self.layout.contains(s)) {
+            rows[seat.row as usize] += 1;
+        }
+        rows
+    }
+
+    // number of taken seats per column, indexed by column
+    pub fn column_occupancy(&self) -> Vec<u32> {
+        let mut columns = vec![0; self.layout.columns as usize];
+        for seat in self.taken.iter().filter(|s| self.layout.contains(s)) {
+            columns[seat.column as usize] += 1;
+        }
+        columns
+    }
+
+    // one line per row, # for taken and . for empty seats, followed by the row's occupancy
+    pub fn to_ascii(&self) -> String {
+        let occupancy = self.row_occupancy();
+        let mut ret = String::new();
+        for row in 0..self.layout.rows {
+            let line = (0..self.layout.columns)
+                .map(|column| if self.taken.contains(&Seat {row, column}) { '#' } else { '.' })
+                .collect::<String>();
+            ret += &format!("{:4} {} {}/{}\n", row, line, occupancy[row as usize], self.layout.columns);
+        }
+        ret
+    }
+
+    // rows go from top to bottom, columns from left to right
+    pub fn to_svg(&self) -> String {
+        let cell = SeatMap::SVG_CELL;
+        let width = self.layout.columns * cell;
+        let height = self.layout.rows * cell;
+        let mut ret = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n", width, height, width, height);
+        for seat in self.layout.seats() {
+            let (fill, state) = if self.taken.contains(&seat) { ("#c0392b", "taken") } else { ("#ecf0f1", "empty") };
+            ret += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#7f8c8d\"><title>row {} column {} id {} {}</title></rect>\n",
+                seat.column * cell, seat.row * cell, cell, cell, fill, seat.row, seat.column, self.layout.get_id(&seat), state);
+        }
+        ret += "</svg>\n";
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use crate::Seat;
+use crate::layout::PlaneLayout;
+use crate::seatmap::SeatMap;
+    #[test]
+    fn small_plane() {
+        let layout = PlaneLayout::from_str("3x2:FB:LR").unwrap();
+        let taken = [(0, 0), (0, 1), (2, 1)].iter().map(|&(row, column)| Seat {row, column}).collect::<BTreeSet<Seat>>();
+        let map = SeatMap::new(&layout, &taken);
+        assert_eq!(map.to_ascii(), "   0 ## 2/2\n   1 .. 0/2\n   2 .# 1/2\n");
+        assert_eq!(map.row_occupancy(), vec![2, 0, 1]);
+        assert_eq!(map.column_occupancy(), vec![1, 2]);
+        let missing = map.missing().iter().map(|s| layout.get_id(s)).collect::<Vec<u32>>();
+        assert_eq!(missing, vec![2, 3, 4]);
+
+        let svg = map.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert_eq!(svg.matches("taken").count(), 3);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+use std::iter::FromIterator;
+
+// A set of answered questions. Questions a-z are kept as bits, so combining answers never allocates.
+// Anything outside a-z falls back to a regular set, which is only used while such a question is included.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Answers {
+    // bit i stands for the letter 'a' + i
+    Letters(u32),
+    Other(BTreeSet<char>),
+}
+
+fn letter_bit(c: char) -> Option<u32> {
+    if c.is_ascii_lowercase() {
+        Some(1 << (c as u32 - 'a' as u32))
+    } else {
+        None
+    }
+}
+
+impl Default for Answers {
+    fn default() -> Self {
+        Answers::Letters(0)
+    }
+}
+
+impl Answers {
+    pub fn new() -> Self {
+        Answers::default()
+    }
+
+    pub fn all_letters() -> Self {
+        Answers::Letters((1 << 26) - 1)
+    }
+
+    // keeps the representation unique, so equality and hashing work across both variants
+    fn from_set(set: BTreeSet<char>) -> Self {
+        match set.iter().map(|&c| letter_bit(c)).sum::<Option<u32>>() {
+            Some(bits) => Answers::Letters(bits),
+            None => Answers::Other(set),
+        }
+    }
+
+    fn to_set(&self) -> BTreeSet<char> {
+        self.iter().collect()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        match (&mut *self, letter_bit(c)) {
+            (Answers::Letters(bits), Some(bit)) => *bits |= bit,
+            (Answers::Other(set), _) => {
+                set.insert(c);
+            },
+            (Answers::Letters(_), None) => {
+                let mut set = self.to_set();
+                set.insert(c);
+                *self = Answers::Other(set);
+            },
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        match (self, letter_bit(c)) {
+            (Answers::Letters(bits), Some(bit)) => bits & bit != 0,
+            (Answers::Letters(_), None) => false,
+            (Answers::Other(set), _) => set.contains(&c),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Answers::Letters(bits) => bits.count_ones() as usize,
+            Answers::Other(set) => set.len(),
+        }
+    }
+
+    // in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        let (bits, set) = match self {
+            Answers::Letters(bits) => (*bits, None),
+            Answers::Other(set) => (0, Some(set)),
+        };
+        (0..26u8).filter(move |i| bits >> i & 1 == 1)
+            .map(|i| (b'a' + i) as char)
+            .chain(set.into_iter().flatten().cloned())
+    }
+
+    pub fn union(&self, other: &Answers) -> Answers {
+        match (self, other) {
+            (Answers::Letters(a), Answers::Letters(b)) => Answers::Letters(a | b),
+            _ => Answers::from_set(&self.to_set() | &other.to_set()),
+        }
+    }
+
+    pub fn intersection(&self, other: &Answers) -> Answers {
+        match (self, other) {
+            (Answers::Letters(a), Answers::Letters(b)) => Answers::Letters(a & b),
+            _ => Answers::from_set(&self.to_set() & &other.to_set()),
+        }
+    }
+
+    pub fn difference(&self, other: &Answers) -> Answers {
+        match (self, other) {
+            (Answers::Letters(a), Answers::Letters(b)) => Answers::Letters(a & !b),
+            _ => Answers::from_set(&self.to_set() - &other.to_set()),
+        }
+    }
+}
+
+impl FromIterator<char> for Answers {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut ret = Answers::new();
+        for c in iter {
+            ret.insert(c);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use crate::answers::Answers;
+    #[test]
+    fn letters() {
+        let a = "abcz".chars().collect::<Answers>();
+        let b = "bcd".chars().collect::<Answers>();
+        assert_eq!(a, Answers::Letters(0b10000000000000000000000111));
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.iter().collect::<String>(), "abcz");
+        assert!(a.contains('z') && !a.contains('d') && !a.contains('A'));
+        assert_eq!(a.union(&b).iter().collect::<String>(), "abcdz");
+        assert_eq!(a.intersection(&b).iter().collect::<String>(), "bc");
+        assert_eq!(a.difference(&b).iter().collect::<String>(), "az");
+        assert_eq!(Answers::all_letters().difference(&a).len(), 22);
+        assert_eq!(Answers::new().len(), 0);
+    }
+
+    #[test]
+    fn other_chars() {
+        let a = "ab1ä".chars().collect::<Answers>();
+        let b = "b1".chars().collect::<Answers>();
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.iter().collect::<String>(), "1abä");
+        assert!(a.contains('ä') && a.contains('a') && !a.contains('c'));
+        assert_eq!(a.intersection(&b), b);
+        // back to bits once only letters are left
+        assert_eq!(a.difference(&"1ä".chars().collect()), Answers::Letters(0b11));
+        assert_eq!(b.union(&"a".chars().collect()).len(), 3);
+    }
+}
@@ -1,7 +1,14 @@
 use std::str::FromStr;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::env;
+use answers::Answers;
+use batch_reader::batches_from_file;
+use query::Query;
+use report::SurveyReport;
+
+mod answers;
+mod query;
+mod report;
 
 #[derive(Debug, PartialEq)]
 struct Group {
@@ -13,81 +20,153 @@ impl Group {
         Group {persons}
     }
 
-    pub fn get_unique_answers(&self) -> HashSet<char> {
-        let ret = self.persons.iter().fold(HashSet::<char>::new(), |acc, x| acc.union(&x.yes_answers).cloned().collect::<HashSet::<char>>());
+    pub fn get_unique_answers(&self) -> Answers {
+        let ret = self.persons.iter().fold(Answers::new(), |acc, x| acc.union(&x.yes_answers));
         ret
     }
 
-    pub fn get_all_answers(&self) -> HashSet<char> {
-        let ret = self.persons.iter().fold(HashSet::<char>::from(self.persons.first().unwrap().yes_answers.iter().cloned().collect()), |acc, x| acc.intersection(&x.yes_answers).cloned().collect::<HashSet::<char>>());
+    // nobody answered anything in a group without persons
+    pub fn get_all_answers(&self) -> Answers {
+        let ret = match self.persons.split_first() {
+            Some((first, rest)) => rest.iter().fold(first.yes_answers.clone(), |acc, x| acc.intersection(&x.yes_answers)),
+            None => Answers::new(),
+        };
+        ret
+    }
+
+    // how many persons answered each question with yes
+    pub fn get_answer_counts(&self) -> HashMap<char, usize> {
+        let mut ret = HashMap::<char, usize>::new();
+        for answer in self.persons.iter().flat_map(|p| p.yes_answers.iter()) {
+            *ret.entry(answer).or_default() += 1;
+        }
         ret
     }
 }
 
 #[derive(Debug, PartialEq)]
 struct Person {
-    pub yes_answers: HashSet<char>,
+    pub yes_answers: Answers,
 }
 
-impl FromStr for Person {
-    type Err = String;
-
-    fn from_str(entry: &str) -> Result<Self, Self::Err> {
-        let mut yes_answers = HashSet::<char>::new();
-        for c in entry.chars() {
+impl Person {
+    // every answer has to be part of the alphabet
+    pub fn parse(entry: &str, alphabet: &Answers) -> Result<Self, String> {
+        let mut yes_answers = Answers::new();
+        for (position, c) in entry.chars().enumerate() {
+            if !alphabet.contains(c) {
+                return Err(format!("Unexpected answer {:?} at position {}.", c, position));
+            }
             yes_answers.insert(c);
         }
 
         Ok(Person {yes_answers})
     }
 }
-fn groups_from_file(filename: &str) -> Vec<Group> {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-    let mut groups = Vec::<Group>::new();
-    let mut current_group_data = Vec::<Person>::new();
-    for line in reader.lines() {
-        let line = line.unwrap();
-        if line.len() == 0 {
-            // end of passport entry, generate new passport
-            if current_group_data.len() > 0 {
-                let group = Group::from(current_group_data);
-                groups.push(group);
-                current_group_data = Vec::<Person>::new();
-            }
 
-            continue;
-        }
-
-        current_group_data.push(Person::from_str(line.trim()).unwrap());
-    }
+// answers are limited to a-z, use Person::parse for other alphabets
+impl FromStr for Person {
+    type Err = String;
 
-    if current_group_data.len() > 0 {
-        let group = Group::from(current_group_data);
-        groups.push(group);
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        Person::parse(entry, &Answers::all_letters())
     }
+}
 
-    groups
+// streams the groups of a file, each line of a batch is one person
+fn groups_from_file(filename: &str, alphabet: Answers) -> impl Iterator<Item = Result<Group, String>> {
+    batches_from_file(filename).unwrap().map(move |batch| {
+        let batch = batch.map_err(|e| e.to_string())?;
+        let persons = batch.lines.iter().enumerate()
+            .map(|(i, line)| Person::parse(line, &alphabet).map_err(|e| format!("Line {}: {}", batch.line + i, e)))
+            .collect::<Result<Vec<Person>, String>>()?;
+        Ok(Group::from(persons))
+    })
 }
 
 fn main() {
-    let groups = groups_from_file("input.txt");
-    let num_unique_answers = groups.iter().fold(0, |acc, x| acc + &x.get_unique_answers().len());
+    // usage: day6 [--alphabet LETTERS] [--query QUERY | --json | --csv frequencies|sizes|cooccurrence] [input]
+    // see Query for the query syntax, the alphabet defaults to a-z
+    let mut input = String::from("input.txt");
+    let mut alphabet = Answers::all_letters();
+    let mut query = None;
+    let mut json = false;
+    let mut csv = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--query" => query = Some(Query::from_str(&args.next().expect("Missing query.")).unwrap()),
+            "--alphabet" => alphabet = args.next().expect("Missing alphabet.").chars().collect(),
+            "--json" => json = true,
+            "--csv" => csv = Some(args.next().expect("Missing table.")),
+            _ => input = arg,
+        }
+    }
+    let input = input.as_str();
+    // malformed groups are reported and left out of all answers
+    let groups = || groups_from_file(input, alphabet.clone()).filter_map(|group| group.map_err(|e| eprintln!("{}", e)).ok());
+
+    if json || csv.is_some() {
+        let report = SurveyReport::from_groups(groups());
+        match csv.as_deref() {
+            None => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Some("frequencies") => print!("{}", report.frequencies_csv()),
+            Some("sizes") => print!("{}", report.group_sizes_csv()),
+            Some("cooccurrence") => print!("{}", report.co_occurrence_csv()),
+            Some(table) => panic!("Unknown table {}.", table),
+        }
+        return;
+    }
+
+    if let Some(query) = query {
+        let mut total = 0;
+        for (i, group) in groups().enumerate() {
+            let answers = query.eval(&group, &alphabet);
+            println!("Group {}: {} {}", i, answers.len(), answers.iter().collect::<String>());
+            total += answers.len();
+        }
+        println!("Total: {}", total);
+        return;
+    }
+
+    let (mut num_unique_answers, mut num_all_answers) = (0, 0);
+    for group in groups() {
+        num_unique_answers += group.get_unique_answers().len();
+        num_all_answers += group.get_all_answers().len();
+    }
     println!("Answer part 1: {}", num_unique_answers);
-    let num_all_answers = groups.iter().fold(0, |acc, x| acc + &x.get_all_answers().len());
     println!("Answer part 2: {}", num_all_answers);
-    }
+}
 
 #[cfg(test)]
 mod tests {
-use crate::groups_from_file;
+use std::str::FromStr;
+use crate::{Group, Person, groups_from_file};
+use crate::answers::Answers;
 #[test]
     fn test_input() {
-        let groups = groups_from_file("testinput.txt");
+        let groups = groups_from_file("testinput.txt", Answers::all_letters()).collect::<Result<Vec<Group>, String>>().unwrap();
         assert_eq!(groups.len(), 5);
-        let num_unique_answers = groups.iter().fold(0, |acc, x| acc + &x.get_unique_answers().len());
+        let num_unique_answers = groups.iter().fold(0, |acc, x| acc + x.get_unique_answers().len());
         assert_eq!(num_unique_answers, 11);
-        let num_all_answers = groups.iter().fold(0, |acc, x| acc + &x.get_all_answers().len());
+        let num_all_answers = groups.iter().fold(0, |acc, x| acc + x.get_all_answers().len());
         assert_eq!(num_all_answers, 6);
     }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(Group::from(vec![]).get_all_answers().len(), 0);
+        assert_eq!(Group::from(vec![]).get_unique_answers().len(), 0);
+        assert_eq!(Person::from_str("ab c"), Err(String::from("Unexpected answer ' ' at position 2.")));
+        assert!(Person::from_str("a1").is_err());
+        assert!(Person::from_str("aB").is_err());
+
+        let alphabet = "xyz".chars().collect::<Answers>();
+        assert!(Person::parse("zyx", &alphabet).is_ok());
+        assert!(Person::parse("a", &alphabet).is_err());
+        let groups = groups_from_file("testinput.txt", alphabet).collect::<Vec<Result<Group, String>>>();
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[1], Err(String::from("Line 3: Unexpected answer 'a' at position 0.")));
+        assert_eq!(groups[2], Err(String::from("Line 7: Unexpected answer 'a' at position 0.")));
+    }
 }
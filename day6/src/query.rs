@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::Group;
+use crate::answers::Answers;
+
+// A question asked of every group, evaluating to the set of questions matching it.
+//
+// query  := term (("or" | "minus") term)*
+// term   := factor ("and" factor)*
+// factor := "not" factor | "(" query ")" | "any" | "all" | "odd"
+//         | "atleast" N | "atmost" N | "exactly" N
+//
+// any is the union and all the intersection of the persons' answers, odd are the answers given by an
+// odd number of persons (the symmetric difference), not is the complement against the alphabet.
+// Count based queries only consider the letters of the alphabet, so atmost and exactly 0 include
+// letters nobody answered.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Query {
+    Any,
+    All,
+    Odd,
+    AtLeast(usize),
+    AtMost(usize),
+    Exactly(usize),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Minus(Box<Query>, Box<Query>),
+}
+
+impl Query {
+    pub fn eval(&self, group: &Group, alphabet: &Answers) -> Answers {
+        self.eval_counted(group, &group.get_answer_counts(), alphabet)
+    }
+
+    // the counts are only computed once per group and shared by all parts of the query
+    fn eval_counted(&self, group: &Group, counts: &HashMap<char, usize>, alphabet: &Answers) -> Answers {
+        let by_count = |f: &dyn Fn(usize) -> bool| {
+            alphabet.iter().filter(|c| f(counts.get(c).cloned().unwrap_or(0))).collect::<Answers>()
+        };
+        let eval = |q: &Query| q.eval_counted(group, counts, alphabet);
+        match self {
+            Query::Any => group.get_unique_answers(),
+            Query::All => group.get_all_answers(),
+            Query::Odd => by_count(&|n| n % 2 == 1),
+            Query::AtLeast(k) => by_count(&|n| n >= *k),
+            Query::AtMost(k) => by_count(&|n| n <= *k),
+            Query::Exactly(k) => by_count(&|n| n == *k),
+            Query::Not(q) => alphabet.difference(&eval(q)),
+            Query::And(a, b) => eval(a).intersection(&eval(b)),
+            Query::Or(a, b) => eval(a).union(&eval(b)),
+            Query::Minus(a, b) => eval(a).difference(&eval(b)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).cloned()
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        let token = self.peek().ok_or_else(|| String::from("Unexpected end of query."))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn query(&mut self) -> Result<Query, String> {
+        let mut ret = self.term()?;
+        while let Some(op) = self.peek().filter(|&t| t == "or" || t == "minus") {
+            self.position += 1;
+            let rhs = Box::new(self.term()?);
+            ret = match op {
+                "or" => Query::Or(Box::new(ret), rhs),
+                _ => Query::Minus(Box::new(ret), rhs),
+            };
+        }
+        Ok(ret)
+    }
+
+    fn term(&mut self) -> Result<Query, String> {
+        let mut ret = self.factor()?;
+        while self.peek() == Some("and") {
+            self.position += 1;
+            ret = Query::And(Box::new(ret), Box::new(self.factor()?));
+        }
+        Ok(ret)
+    }
+
+    fn count(&mut self) -> Result<usize, String> {
+        let token = self.next()?;
+        usize::from_str(token).map_err(|_| format!("Expected a count, found {}.", token))
+    }
+
+    fn factor(&mut self) -> Result<Query, String> {
+        match self.next()? {
+            "not" => Ok(Query::Not(Box::new(self.factor()?))),
+            "(" => {
+                let ret = self.query()?;
+                match self.next()? {
+                    ")" => Ok(ret),
+                    token => Err(format!("Expected ), found {}.", token)),
+                }
+            },
+            "any" => Ok(Query::Any),
+            "all" => Ok(Query::All),
+            "odd" => Ok(Query::Odd),
+            "atleast" => Ok(Query::AtLeast(self.count()?)),
+            "atmost" => Ok(Query::AtMost(self.count()?)),
+            "exactly" => Ok(Query::Exactly(self.count()?)),
+            token => Err(format!("Unexpected {} in query.", token)),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let spaced = entry.replace('(', " ( ").replace(')', " ) ");
+        let mut parser = Parser {tokens: spaced.split_whitespace().collect(), position: 0};
+        let ret = parser.query()?;
+        match parser.peek() {
+            Some(token) => Err(format!("Unexpected {} after query.", token)),
+            None => Ok(ret),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::{Group, Person};
+use crate::answers::Answers;
+use crate::query::Query;
+    fn group(persons: &[&str]) -> Group {
+        Group::from(persons.iter().map(|p| Person::from_str(p).unwrap()).collect())
+    }
+
+    fn eval(query: &str, group: &Group) -> Vec<char> {
+        Query::from_str(query).unwrap().eval(group, &Answers::all_letters()).iter().collect()
+    }
+
+    #[test]
+    fn queries() {
+        let group = group(&["abc", "ab", "bd"]);
+        assert_eq!(eval("any", &group), vec!['a', 'b', 'c', 'd']);
+        assert_eq!(eval("all", &group), vec!['b']);
+        assert_eq!(eval("exactly 1", &group), vec!['c', 'd']);
+        assert_eq!(eval("atleast 2", &group), vec!['a', 'b']);
+        assert_eq!(eval("odd", &group), vec!['b', 'c', 'd']);
+        assert_eq!(eval("atmost 1 and any", &group), vec!['c', 'd']);
+        assert_eq!(eval("any minus (all or exactly 1)", &group), vec!['a']);
+        assert_eq!(eval("not any", &group).len(), 22);
+        assert_eq!(eval("not not all", &group), vec!['b']);
+        assert_eq!(eval("atleast 0", &group).len(), 26);
+        assert_eq!(eval("exactly 0", &group).len(), 22);
+        assert_eq!(Query::from_str("any or all and odd"), Query::from_str("any or (all and odd)"));
+        assert_eq!(Query::from_str("any").unwrap().eval(&group, &Answers::all_letters()), group.get_unique_answers());
+        assert_eq!(Query::from_str("exactly 3").unwrap().eval(&group, &Answers::all_letters()), "b".chars().collect::<Answers>());
+    }
+
+    #[test]
+    fn alphabet() {
+        let alphabet = "xyz".chars().collect::<Answers>();
+        let group = Group::from(["xy", "x"].iter().map(|p| Person::parse(p, &alphabet).unwrap()).collect());
+        let eval = |query| Query::from_str(query).unwrap().eval(&group, &alphabet).iter().collect::<String>();
+        assert_eq!(eval("not any"), "z");
+        assert_eq!(eval("exactly 0"), "z");
+        assert_eq!(eval("atmost 1"), "yz");
+        assert_eq!(eval("not all"), "yz");
+    }
+
+    #[test]
+    fn invalid_queries() {
+        for bad in ["", "any or", "(any", "any)", "exactly", "exactly x", "some", "any all"].iter() {
+            assert!(Query::from_str(bad).is_err(), "{} accepted", bad);
+        }
+    }
+}
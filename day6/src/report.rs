@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::Group;
+
+// Statistics over all groups of a survey, gathered one group at a time.
+#[derive(Debug, PartialEq, Default, Serialize)]
+pub struct SurveyReport {
+    pub groups: usize,
+    pub persons: usize,
+    // question -> number of persons answering yes
+    pub person_frequencies: BTreeMap<char, usize>,
+    // question -> number of groups in which anyone answered yes
+    pub group_frequencies: BTreeMap<char, usize>,
+    // group size -> number of groups
+    pub group_sizes: BTreeMap<usize, usize>,
+    // question -> other question -> number of persons answering yes to both
+    pub co_occurrence: BTreeMap<char, BTreeMap<char, usize>>,
+}
+
+impl SurveyReport {
+    pub fn add(&mut self, group: &Group) {
+        self.groups += 1;
+        self.persons += group.persons.len();
+        *self.group_sizes.entry(group.persons.len()).or_default() += 1;
+        for question in group.get_unique_answers().iter() {
+            *self.group_frequencies.entry(question).or_default() += 1;
+        }
+        for person in group.persons.iter() {
+            for question in person.yes_answers.iter() {
+                *self.person_frequencies.entry(question).or_default() += 1;
+                for other in person.yes_answers.iter().filter(|&o| o != question) {
+                    *self.co_occurrence.entry(question).or_default().entry(other).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    pub fn from_groups<I: IntoIterator<Item = Group>>(groups: I) -> Self {
+        let mut ret = SurveyReport::default();
+        for group in groups {
+            ret.add(&group);
+        }
+        ret
+    }
+
+    pub fn frequencies_csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(Vec::<u8>::new());
+        writer.write_record(["question", "persons", "groups"]).unwrap();
+        for (question, persons) in self.person_frequencies.iter() {
+            let groups = self.group_frequencies.get(question).cloned().unwrap_or(0);
+            writer.write_record([question.to_string(), persons.to_string(), groups.to_string()]).unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    pub fn group_sizes_csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(Vec::<u8>::new());
+        writer.write_record(["size", "groups"]).unwrap();
+        for (size, groups) in self.group_sizes.iter() {
+            writer.write_record([size.to_string(), groups.to_string()]).unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    // full square matrix over all answered questions, the diagonal holds the person frequencies
+    pub fn co_occurrence_csv(&self) -> String {
+        let questions = self.person_frequencies.keys().cloned().collect::<Vec<char>>();
+        let mut writer = csv::Writer::from_writer(Vec::<u8>::new());
+        let header = std::iter::once(String::from("question")).chain(questions.iter().map(|q| q.to_string()));
+        writer.write_record(header).unwrap();
+        for question in questions.iter() {
+            let counts = questions.iter().map(|other| match other == question {
+                true => self.person_frequencies[question],
+                false => self.co_occurrence.get(question).and_then(|c| c.get(other)).cloned().unwrap_or(0),
+            });
+            let row = std::iter::once(question.to_string()).chain(counts.map(|c| c.to_string()));
+            writer.write_record(row).unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use crate::groups_from_file;
+use crate::answers::Answers;
+use crate::report::SurveyReport;
+    #[test]
+    fn test_input() {
+        let report = SurveyReport::from_groups(groups_from_file("testinput.txt", Answers::all_letters()).map(Result::unwrap));
+        assert_eq!((report.groups, report.persons), (5, 11));
+        assert_eq!(report.person_frequencies[&'a'], 8);
+        assert_eq!(report.group_frequencies[&'a'], 4);
+        assert_eq!(report.group_sizes.iter().map(|(&s, &c)| (s, c)).collect::<Vec<_>>(), vec![(1, 2), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!(report.co_occurrence[&'a'][&'b'], 2);
+        assert_eq!(report.co_occurrence[&'b'][&'a'], 2);
+        assert!(!report.co_occurrence[&'a'].contains_key(&'a'));
+
+        assert_eq!(report.frequencies_csv(), "question,persons,groups\na,8,4\nb,4,4\nc,3,3\n");
+        assert_eq!(report.group_sizes_csv(), "size,groups\n1,2\n2,1\n3,1\n4,1\n");
+        assert_eq!(report.co_occurrence_csv(), "question,a,b,c\na,8,2,2\nb,2,4,1\nc,2,1,3\n");
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["person_frequencies"]["a"], 8);
+        assert_eq!(json["group_sizes"]["4"], 1);
+    }
+}
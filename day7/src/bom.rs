@@ -0,0 +1,139 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::Bag;
+use crate::graph::{BagGraph, BagId};
+use crate::parser::parse_bag_name;
+
+// A number of bags of several kinds, written like "3 shiny gold + 2 dark red".
+#[derive(Debug, PartialEq)]
+pub struct Order {
+    pub items: Vec<(u32, Bag)>,
+}
+
+impl FromStr for Order {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        let mut items = Vec::<(u32, Bag)>::new();
+        for item in entry.split('+') {
+            let item = item.trim();
+            let (count, name) = item.split_at(item.find(' ').ok_or(format!("Expected a count and a bag in \"{}\".", item))?);
+            let count = u32::from_str(count).map_err(|e| format!("Invalid count {}: {}", count, e))?;
+            items.push((count, parse_bag_name(name)?));
+        }
+        Ok(Order {items})
+    }
+}
+
+// One kind of bag to pack, its contents are packed by earlier steps.
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub bag: BagId,
+    pub count: u128,
+    pub contents: Vec<(BagId, u32)>,
+}
+
+// Everything needed to fulfil an order.
+#[derive(Debug)]
+pub struct Bom<'a> {
+    graph: &'a BagGraph,
+    // total bags of every kind including the ordered ones, sorted by bag
+    pub inventory: Vec<(BagId, u128)>,
+    // the most levels of bags nested inside each other
+    pub depth: usize,
+    // the innermost bags first, so every bag is packed after its contents
+    pub plan: Vec<Step>,
+}
+
+impl<'a> Bom<'a> {
+    pub fn new(graph: &'a BagGraph, order: &Order) -> Result<Self, String> {
+        let mut copies = vec![0u128; graph.len()];
+        let mut starts = Vec::<BagId>::new();
+        for (count, bag) in order.items.iter() {
+            let id = graph.id(bag).ok_or(format!("No rule mentions {}.", bag))?;
+            copies[id] += *count as u128;
+            starts.push(id);
+        }
+        let packing = graph.post_order(&starts)?;
+
+        for &current in packing.iter().rev() {
+            for &(inner, count) in graph.inner(current).iter() {
+                copies[inner] = copies[current].checked_mul(count as u128)
+                    .and_then(|c| c.checked_add(copies[inner]))
+                    .ok_or_else(|| format!("Number of {} bags does not fit into 128 bits.", graph.bag(inner)))?;
+            }
+        }
+
+        let mut levels = vec![0usize; graph.len()];
+        for &current in packing.iter() {
+            levels[current] = 1 + graph.inner(current).iter().map(|&(inner, _)| levels[inner]).max().unwrap_or(0);
+        }
+        let depth = starts.iter().map(|&id| levels[id]).max().unwrap_or(0);
+
+        let plan = packing.iter().map(|&bag| Step {bag, count: copies[bag], contents: graph.inner(bag).to_vec()}).collect();
+        let mut inventory = packing.into_iter().map(|bag| (bag, copies[bag])).collect::<Vec<(BagId, u128)>>();
+        inventory.sort_by(|a, b| graph.bag(a.0).cmp(graph.bag(b.0)));
+        Ok(Bom {graph, inventory, depth, plan})
+    }
+
+    pub fn total(&self) -> u128 {
+        self.inventory.iter().map(|&(_, count)| count).sum()
+    }
+}
+
+impl fmt::Display for Bom<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} bags, nested {} levels deep", self.total(), self.depth)?;
+        for &(id, count) in self.inventory.iter() {
+            writeln!(f, "{:>8} {}", count, self.graph.bag(id))?;
+        }
+        writeln!(f, "packing plan:")?;
+        for (i, step) in self.plan.iter().enumerate() {
+            write!(f, "{:>4}. {} {}", i + 1, step.count, self.graph.bag(step.bag))?;
+            if !step.contents.is_empty() {
+                let contents = step.contents.iter().map(|&(id, count)| format!("{} {}", count, self.graph.bag(id))).collect::<Vec<String>>();
+                write!(f, ", each with {}", contents.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use std::str::FromStr;
+use crate::{Bag, get_rules_from_file};
+use crate::bom::{Bom, Order};
+use crate::graph::BagGraph;
+use crate::testing::id;
+    #[test]
+    fn order() {
+        let order = Order::from_str("3 shiny gold + 2 dark red bags").unwrap();
+        assert_eq!(order.items, vec![(3, Bag::from_str("shiny gold bag").unwrap()), (2, Bag::from_str("dark red bag").unwrap())]);
+        assert!(Order::from_str("shiny gold").is_err());
+        assert!(Order::from_str("3 shiny gold +").is_err());
+    }
+
+    #[test]
+    fn bill_of_materials() {
+        let graph = BagGraph::from_rules(&get_rules_from_file("testinput.txt").unwrap());
+        let bom = Bom::new(&graph, &Order::from_str("2 shiny gold + 1 dark olive").unwrap()).unwrap();
+        // 2 shiny gold hold 2 * 32 bags, the extra dark olive another 7
+        assert_eq!(bom.total(), 2 + 64 + 1 + 7);
+        assert_eq!(bom.depth, 3);
+        let count = |name: &str| bom.inventory.iter().find(|&&(bag, _)| bag == id(&graph, name)).unwrap().1;
+        assert_eq!(count("dark olive bag"), 3);
+        assert_eq!(count("faded blue bag"), 3 * 3 + 2 * 2 * 5);
+        let position = |name: &str| bom.plan.iter().position(|step| step.bag == id(&graph, name)).unwrap();
+        assert!(position("faded blue bag") < position("dark olive bag"));
+        assert!(position("dark olive bag") < position("shiny gold bag"));
+        assert!(bom.to_string().starts_with("74 bags, nested 3 levels deep\n"));
+    }
+
+    #[test]
+    fn unknown_bag() {
+        let graph = BagGraph::from_rules(&get_rules_from_file("testinput.txt").unwrap());
+        assert!(Bom::new(&graph, &Order::from_str("1 plaid purple").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::graph::{BagGraph, BagId};
+
+// Which part of the graph to export.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Scope {
+    All,
+    // the bag and everything inside it
+    From(BagId),
+    // the bag and everything containing it
+    To(BagId),
+}
+
+// The bags in scope in the order of Bag, and the edges between them with their counts.
+fn select(graph: &BagGraph, scope: Scope) -> (Vec<BagId>, Vec<(BagId, BagId, u32)>) {
+    let mut bags = match scope {
+        Scope::All => (0..graph.len()).collect(),
+        Scope::From(id) => graph.contents(id),
+        Scope::To(id) => graph.containers(id),
+    };
+    if let Scope::From(id) | Scope::To(id) = scope {
+        if !bags.contains(&id) {
+            bags.push(id);
+        }
+    }
+    bags.sort_by(|&a, &b| graph.bag(a).cmp(graph.bag(b)));
+    let mut selected = vec![false; graph.len()];
+    for &id in bags.iter() {
+        selected[id] = true;
+    }
+    let edges = bags.iter()
+        .flat_map(|&outer| graph.inner(outer).iter().map(move |&(inner, count)| (outer, inner, count)))
+        .filter(|&(_, inner, _)| selected[inner])
+        .collect();
+    (bags, edges)
+}
+
+// Bag names may contain any character but spaces, commas and periods, so labels need escaping.
+fn dot_label(graph: &BagGraph, id: BagId) -> String {
+    graph.bag(id).to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Mermaid has no backslash escapes, it uses entity codes instead, which in turn start with #.
+fn mermaid_label(graph: &BagGraph, id: BagId) -> String {
+    graph.bag(id).to_string().replace('#', "#35;").replace('"', "#quot;").replace('\\', "#92;")
+}
+
+pub fn to_dot(graph: &BagGraph, scope: Scope) -> String {
+    let (bags, edges) = select(graph, scope);
+    let mut ret = String::from("digraph bags {\n");
+    for id in bags {
+        ret += &format!("    b{} [label=\"{}\"];\n", id, dot_label(graph, id));
+    }
+    for (outer, inner, count) in edges {
+        ret += &format!("    b{} -> b{} [label=\"{}\"];\n", outer, inner, count);
+    }
+    ret += "}\n";
+    ret
+}
+
+pub fn to_mermaid(graph: &BagGraph, scope: Scope) -> String {
+    let (bags, edges) = select(graph, scope);
+    let mut ret = String::from("graph TD\n");
+    for id in bags {
+        ret += &format!("    b{}[\"{}\"]\n", id, mermaid_label(graph, id));
+    }
+    for (outer, inner, count) in edges {
+        ret += &format!("    b{} -->|{}| b{}\n", outer, count, inner);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+use crate::export::{Scope, to_dot, to_mermaid};
+use crate::graph::BagGraph;
+use crate::testing;
+    fn graph() -> BagGraph {
+        testing::graph(&[
+            "light red bags contain 1 shiny gold bag.",
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain no other bags.",
+        ])
+    }
+
+    #[test]
+    fn dot() {
+        let graph = graph();
+        assert_eq!(to_dot(&graph, Scope::All), "digraph bags {
+    b1 [label=\"shiny gold\"];
+    b2 [label=\"dark red\"];
+    b0 [label=\"light red\"];
+    b1 -> b2 [label=\"2\"];
+    b0 -> b1 [label=\"1\"];
+}
+");
+    }
+
+    #[test]
+    fn mermaid() {
+        let graph = graph();
+        assert_eq!(to_mermaid(&graph, Scope::From(1)), "graph TD
+    b1[\"shiny gold\"]
+    b2[\"dark red\"]
+    b1 -->|2| b2
+");
+        assert_eq!(to_mermaid(&graph, Scope::To(1)), "graph TD
+    b1[\"shiny gold\"]
+    b0[\"light red\"]
+    b0 -->|1| b1
+");
+    }
+
+    #[test]
+    fn escaping() {
+        let graph = testing::graph(&[r#"sh"iny \#gold bags contain no other bags."#]);
+        assert_eq!(to_dot(&graph, Scope::All), "digraph bags {\n    b0 [label=\"sh\\\"iny \\\\#gold\"];\n}\n");
+        assert_eq!(to_mermaid(&graph, Scope::All), "graph TD\n    b0[\"sh#quot;iny #92;#35;gold\"]\n");
+    }
+}
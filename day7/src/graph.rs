@@ -0,0 +1,290 @@
+use std::collections::{HashMap, VecDeque};
+use crate::{Bag, Rule};
+
+pub type BagId = usize;
+
+// The rules as a directed graph, every bag interned once. Edges point from the outer to the inner
+// bag and carry the count, the reverse edges allow walking from a bag to everything containing it.
+#[derive(Debug)]
+pub struct BagGraph {
+    bags: Vec<Bag>,
+    ids: HashMap<Bag, BagId>,
+    inner: Vec<Vec<(BagId, u32)>>,
+    outer: Vec<Vec<(BagId, u32)>>,
+    defined: Vec<bool>,
+    duplicates: Vec<BagId>,
+}
+
+impl BagGraph {
+    // Bags that are only referenced are added without contents. If a bag is defined more than once,
+    // only its first rule is used.
+    pub fn from_rules(rules: &[Rule]) -> Self {
+        let mut graph = BagGraph {bags: Vec::new(), ids: HashMap::new(), inner: Vec::new(), outer: Vec::new(), defined: Vec::new(), duplicates: Vec::new()};
+        for rule in rules.iter() {
+            let outer = graph.intern(&rule.outer_bag);
+            if graph.defined[outer] {
+                graph.duplicates.push(outer);
+                continue;
+            }
+            graph.defined[outer] = true;
+            for (count, bag) in rule.inner_bags.iter() {
+                let inner = graph.intern(bag);
+                graph.inner[outer].push((inner, *count));
+                graph.outer[inner].push((outer, *count));
+            }
+        }
+        graph
+    }
+
+    fn intern(&mut self, bag: &Bag) -> BagId {
+        if let Some(&id) = self.ids.get(bag) {
+            return id;
+        }
+        let id = self.bags.len();
+        self.bags.push(bag.clone());
+        self.ids.insert(bag.clone(), id);
+        self.inner.push(Vec::new());
+        self.outer.push(Vec::new());
+        self.defined.push(false);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.bags.len()
+    }
+
+    pub fn id(&self, bag: &Bag) -> Option<BagId> {
+        self.ids.get(bag).cloned()
+    }
+
+    pub fn bag(&self, id: BagId) -> &Bag {
+        &self.bags[id]
+    }
+
+    pub fn is_defined(&self, id: BagId) -> bool {
+        self.defined[id]
+    }
+
+    // bags with more than one rule, once for every ignored rule
+    pub fn duplicates(&self) -> &[BagId] {
+        &self.duplicates
+    }
+
+    // the bags directly inside the given one, with their counts
+    pub fn inner(&self, id: BagId) -> &[(BagId, u32)] {
+        &self.inner[id]
+    }
+
+    // the bags directly containing the given one, with their counts
+    pub fn outer(&self, id: BagId) -> &[(BagId, u32)] {
+        &self.outer[id]
+    }
+
+    // Walks the edges given by next from start, visiting every bag at most once.
+    // The result does not include start, unless it can be reached from itself.
+    pub fn reachable<'a, F>(&'a self, start: BagId, next: F) -> Vec<BagId>
+        where F: Fn(BagId) -> &'a [(BagId, u32)] {
+        let mut seen = vec![false; self.len()];
+        let mut ret = Vec::<BagId>::new();
+        let mut queue = vec![start];
+        while let Some(current) = queue.pop() {
+            for &(neighbour, _) in next(current).iter() {
+                if !seen[neighbour] {
+                    seen[neighbour] = true;
+                    ret.push(neighbour);
+                    queue.push(neighbour);
+                }
+            }
+        }
+        ret
+    }
+
+    // every bag that eventually contains the given one
+    pub fn containers(&self, id: BagId) -> Vec<BagId> {
+        self.reachable(id, |i| self.outer(i))
+    }
+
+    // every bag eventually inside the given one
+    pub fn contents(&self, id: BagId) -> Vec<BagId> {
+        self.reachable(id, |i| self.inner(i))
+    }
+
+    // A shortest way to pack to inside from, with the count at every step.
+    pub fn path(&self, from: BagId, to: BagId) -> Option<Vec<(BagId, u32)>> {
+        let mut parent: Vec<Option<(BagId, u32)>> = vec![None; self.len()];
+        let mut queue = VecDeque::from(vec![from]);
+        while let Some(current) = queue.pop_front() {
+            for &(inner, count) in self.inner(current).iter() {
+                if inner != from && parent[inner].is_none() {
+                    parent[inner] = Some((current, count));
+                    queue.push_back(inner);
+                }
+            }
+        }
+        parent[to]?;
+        let mut ret = Vec::new();
+        let mut current = to;
+        while let Some((outer, count)) = parent[current] {
+            ret.push((current, count));
+            if outer == from {
+                break;
+            }
+            current = outer;
+        }
+        ret.reverse();
+        Some(ret)
+    }
+
+    // The longest chain of bags nested in each other, starting with the given bag.
+    pub fn deepest_chain(&self, id: BagId) -> Result<Vec<BagId>, String> {
+        let mut depth = vec![0usize; self.len()];
+        let mut next: Vec<Option<BagId>> = vec![None; self.len()];
+        for current in self.post_order(&[id])? {
+            // on ties the first bag of the rule wins
+            for &(inner, _) in self.inner(current).iter() {
+                if next[current].is_none() || depth[inner] + 1 > depth[current] {
+                    depth[current] = depth[inner] + 1;
+                    next[current] = Some(inner);
+                }
+            }
+        }
+        let mut ret = vec![id];
+        while let Some(inner) = next[*ret.last().unwrap()] {
+            ret.push(inner);
+        }
+        Ok(ret)
+    }
+
+    // The given bags and everything inside them, every bag after all bags it contains.
+    // Runs without recursion, so deep nesting cannot overflow the stack.
+    pub fn post_order(&self, starts: &[BagId]) -> Result<Vec<BagId>, String> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            Open,
+            Done,
+        }
+        let mut state = vec![State::New; self.len()];
+        let mut order = Vec::new();
+        let mut stack = starts.iter().rev().map(|&id| (id, false)).collect::<Vec<(BagId, bool)>>();
+        while let Some((current, expanded)) = stack.pop() {
+            if expanded {
+                state[current] = State::Done;
+                order.push(current);
+                continue;
+            }
+            if state[current] == State::Done {
+                continue;
+            }
+            state[current] = State::Open;
+            stack.push((current, true));
+            for &(inner, _) in self.inner(current).iter() {
+                match state[inner] {
+                    State::New => stack.push((inner, false)),
+                    State::Open => return Err(format!("{} contains itself.", self.bag(inner))),
+                    State::Done => (),
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    // Number of bags inside the given one, each shared bag is only computed once.
+    pub fn required_bags(&self, id: BagId) -> Result<u128, String> {
+        let mut required = vec![0u128; self.len()];
+        for current in self.post_order(&[id])? {
+            let mut total = 0u128;
+            for &(inner, count) in self.inner(current).iter() {
+                total = required[inner].checked_add(1)
+                    .and_then(|r| r.checked_mul(count as u128))
+                    .and_then(|r| r.checked_add(total))
+                    .ok_or_else(|| format!("Number of bags inside {} does not fit into 128 bits.", self.bag(id)))?;
+            }
+            required[current] = total;
+        }
+        Ok(required[id])
+    }
+
+    // How many bags of each kind are inside the given one in total, sorted by bag.
+    pub fn breakdown(&self, id: BagId) -> Result<Vec<(BagId, u128)>, String> {
+        let order = self.post_order(&[id])?;
+        let mut copies = vec![0u128; self.len()];
+        copies[id] = 1;
+        // outer bags first, so all copies of a bag are known before it is opened
+        for &current in order.iter().rev() {
+            for &(inner, count) in self.inner(current).iter() {
+                copies[inner] = copies[current].checked_mul(count as u128)
+                    .and_then(|c| c.checked_add(copies[inner]))
+                    .ok_or_else(|| format!("Number of {} bags inside {} does not fit into 128 bits.", self.bag(inner), self.bag(id)))?;
+            }
+        }
+        let mut ret = order.into_iter().filter(|&bag| bag != id).map(|bag| (bag, copies[bag])).collect::<Vec<(BagId, u128)>>();
+        ret.sort_by(|a, b| self.bag(a.0).cmp(self.bag(b.0)));
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use crate::graph::BagGraph;
+use crate::testing::{chain, graph, id};
+
+    #[test]
+    fn adjacency() {
+        let graph = graph(&[
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "muted yellow bags contain 2 shiny gold bags.",
+            "muted yellow bags contain no other bags.",
+        ]);
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.duplicates().len(), 1);
+        let (gold, red) = (id(&graph, "shiny gold"), id(&graph, "light red"));
+        assert!(!graph.is_defined(gold));
+        assert_eq!(graph.inner(gold), &[]);
+        assert_eq!(graph.outer(gold).len(), 2);
+        assert_eq!(graph.inner(red).len(), 2);
+        assert_eq!(graph.containers(gold).len(), 3);
+        assert_eq!(graph.contents(red).len(), 3);
+        assert_eq!(graph.required_bags(red), Ok(8));
+        assert_eq!(graph.required_bags(gold), Ok(0));
+        let (white, yellow) = (id(&graph, "bright white"), id(&graph, "muted yellow"));
+        let mut expected = vec![(gold, 5), (white, 1), (yellow, 2)];
+        expected.sort_by(|a, b| graph.bag(a.0).cmp(graph.bag(b.0)));
+        assert_eq!(graph.breakdown(red), Ok(expected));
+        assert_eq!(graph.path(red, gold), Some(vec![(white, 1), (gold, 1)]));
+        assert_eq!(graph.path(gold, red), None);
+        assert_eq!(graph.deepest_chain(red), Ok(vec![red, white, gold]));
+        assert_eq!(graph.deepest_chain(gold), Ok(vec![gold]));
+    }
+
+    #[test]
+    fn deep_chain() {
+        let graph = BagGraph::from_rules(&chain(100000, 1));
+        let (first, last) = (id(&graph, "m0 c"), id(&graph, "m100000 c"));
+        assert_eq!(graph.required_bags(first), Ok(100000));
+        assert_eq!(graph.containers(last).len(), 100000);
+        assert_eq!(graph.deepest_chain(first).unwrap().len(), 100001);
+        assert_eq!(graph.path(first, last).unwrap().len(), 100000);
+    }
+
+    #[test]
+    fn large_counts() {
+        // every level holds 1000 bags of the next, which is far beyond u32 after a few levels
+        let graph = BagGraph::from_rules(&chain(12, 1000));
+        let (first, last) = (id(&graph, "m0 c"), id(&graph, "m12 c"));
+        assert_eq!(graph.required_bags(first), Ok((1..=12).map(|i| 1000u128.pow(i)).sum()));
+        assert_eq!(graph.breakdown(first).unwrap().iter().find(|b| b.0 == last).unwrap().1, 1000u128.pow(12));
+
+        let graph = BagGraph::from_rules(&chain(14, 1000));
+        assert!(graph.required_bags(id(&graph, "m0 c")).is_err());
+    }
+
+    #[test]
+    fn cycle() {
+        let graph = graph(&["a b bags contain 1 c d bag.", "c d bags contain 2 a b bags."]);
+        assert!(graph.required_bags(0).is_err());
+        assert!(graph.breakdown(0).is_err());
+        assert_eq!(graph.containers(0).len(), 2);
+    }
+}
@@ -1,10 +1,24 @@
 use std::str::FromStr;
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use regex::Regex;
-use lazy_static::lazy_static;
-use const_format::formatcp;
-use std::collections::HashSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use bom::{Bom, Order};
+use export::{Scope, to_dot, to_mermaid};
+use graph::BagGraph;
+use parser::parse_bag_name;
+use shell::Shell;
+use validation::validate;
+
+mod bom;
+mod export;
+mod graph;
+mod parser;
+mod shell;
+#[cfg(test)]
+mod testing;
+mod validation;
 
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash, Ord, PartialOrd)]
@@ -13,8 +27,13 @@ struct Bag {
     modifier: String
 }
 
-impl Bag {
-    const BAG_REGEX_STR: &'static str = r"(\w+) (\w+) bags?";
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifier.is_empty() {
+            return write!(f, "{}", self.color);
+        }
+        write!(f, "{} {}", self.modifier, self.color)
+    }
 }
 
 
@@ -22,22 +41,25 @@ impl FromStr for Bag {
     type Err = String;
 
     fn from_str(entry: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref BAG_REGEX : Regex = Regex::new(Bag::BAG_REGEX_STR).unwrap();
-        }
-        let cap = BAG_REGEX.captures(entry).ok_or(format!("Regex not matched for {}", entry))?;
-        let modifier = cap.get(1).unwrap().as_str();
-        let color = cap.get(2).unwrap().as_str();
-
-        let modifier = String::from(modifier);
-        let color = String::from(color);
+        parser::parse_bag(entry)
+    }
+}
 
+// In JSON a bag is just its name, like "shiny gold".
+impl Serialize for Bag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
 
-        Ok(Bag {color, modifier})
+impl<'de> Deserialize<'de> for Bag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        parser::parse_bag(&format!("{} bags", name)).map_err(serde::de::Error::custom)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Rule {
     outer_bag: Bag,
     inner_bags: Vec<(u32, Bag)>,
@@ -47,107 +69,215 @@ impl FromStr for Rule {
     type Err = String;
 
     fn from_str(entry: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref BAG_REGEX : Regex = Regex::new(Rule::RULE_REGEX_STR).unwrap();
-            static ref INNER_BAG_REGEX : Regex = Regex::new(Rule::INNER_RULE_REGEX_STR).unwrap();
+        parser::parse_rule(entry)
+    }
+}
+
+// Writes the rule in the puzzle grammar, so it can be parsed again.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bags contain ", self.outer_bag)?;
+        if self.inner_bags.is_empty() {
+            return write!(f, "no other bags.");
         }
-        let cap = BAG_REGEX.captures(entry).ok_or(format!("Regex not matched for {}", entry))?;
-        let outer_bag = &cap["outer"];
-        let outer_bag = Bag::from_str(outer_bag)?;
-        let mut inner_bags = Vec::<(u32, Bag)>::new();
-        let inner_str = &cap["inners"];
-        for cap in INNER_BAG_REGEX.captures_iter(inner_str) {
-            let count = u32::from_str(&cap["count"]).unwrap();
-            let bag = Bag::from_str(&cap["bag"]).unwrap();
-            inner_bags.push((count, bag));
+        for (i, (count, bag)) in self.inner_bags.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {} {}", count, bag, if *count == 1 { "bag" } else { "bags" })?;
         }
-        Ok(Rule {outer_bag, inner_bags})
+        write!(f, ".")
     }
 }
 
 impl Rule {
-    const RULE_REGEX_STR: &'static str = formatcp!(r"^(?P<outer>{0}) contain (?P<inners>(?:\d {0}(?:, )?)*)|(?:no other bags)\.$", Bag::BAG_REGEX_STR);
-    const INNER_RULE_REGEX_STR: &'static str = formatcp!(r"(?P<count>\d) (?P<bag>{0})", Bag::BAG_REGEX_STR);
-
+    #[cfg(test)]
     pub fn can_contain(&self, bag: &Bag) -> bool {
-        return self.inner_bags.iter().any(|x| &x.1 == bag)
+        self.inner_bags.iter().any(|x| &x.1 == bag)
     }
 }
 
-fn get_rules_from_file(filename: &str) -> Vec::<Rule> {
-    let file = File::open(filename).unwrap();
+// Fails on the first rule that cannot be parsed, naming its line and column.
+fn get_rules_from_file(filename: &str) -> Result<Vec::<Rule>, String> {
+    let file = File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
     let reader = BufReader::new(file);
     let mut entries = Vec::<Rule>::new();
-    for line in reader.lines() {
-        let entry = Rule::from_str(&line.unwrap()).unwrap();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", filename, e))?;
+        let entry = Rule::from_str(&line).map_err(|e| format!("{}: line {}, {}", filename, i + 1, e))?;
         entries.push(entry);
     }
 
-    entries 
+    Ok(entries)
 }
 
-// we use a wrapper to sort and dedup exactly once instead of during recursing
-fn get_valid_bags<'a>(rules: &'a Vec<Rule>, bag: &Bag) -> Vec<&'a Bag> {
-    let mut ret = get_valid_bags_inner(rules, bag);
-    ret.sort();
-    ret.dedup();
-    ret
+fn get_rules_from_json(filename: &str) -> Result<Vec::<Rule>, String> {
+    let file = File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("{}: {}", filename, e))
 }
 
-fn get_valid_bags_inner<'a>(rules: &'a Vec<Rule>, bag: &Bag) -> Vec<&'a Bag> {
-    let mut ret = <Vec::<&Bag>>::new();
-    let mut seen_bags = HashSet::<&Bag>::new();
-    let mut queue = vec![bag];
-    while queue.len() > 0 {
-        let current = queue.pop().unwrap();
-        if seen_bags.contains(current) {
-            continue;
-        }
-        seen_bags.insert(current);
-
-        // this gets all bags that can contain the current bag directly
-        let valid_bags = rules.iter().filter_map(|x| 
-            if x.can_contain(current) {
-                Some(&x.outer_bag)
-            } else {
-                None
-            }).collect::<Vec::<&Bag>>();
-
-        // now loop all not already processed bags to find transitive valid bags
-        for bag in valid_bags.iter() {
-            if !seen_bags.contains(bag) {
-                let valid_bags = get_valid_bags(rules, bag);
-                ret.extend(valid_bags);
+// Sorts rules by their outer bag and the contents of each rule by bag, so equal rule sets are
+// written the same way. Repeated rules for a bag are dropped, the first one is kept like in
+// BagGraph, and a bag listed twice inside one rule is merged into one count.
+fn normalize(mut rules: Vec<Rule>) -> Result<Vec<Rule>, String> {
+    rules.sort_by(|a, b| a.outer_bag.cmp(&b.outer_bag));
+    rules.dedup_by(|later, first| later.outer_bag == first.outer_bag);
+    for rule in rules.iter_mut() {
+        rule.inner_bags.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut merged = Vec::<(u32, Bag)>::new();
+        for (count, bag) in rule.inner_bags.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.1 == bag => {
+                    last.0 = last.0.checked_add(count)
+                        .ok_or(format!("{} bags contain more {} bags than fit into 32 bits.", rule.outer_bag, bag))?;
+                },
+                _ => merged.push((count, bag)),
             }
         }
-        ret.extend(valid_bags);
+        rule.inner_bags = merged;
     }
-    ret
+    Ok(rules)
 }
 
-fn get_required_bags(rules: &Vec<Rule>, bag: &Bag) -> u32 {
-    let mut ret:u32 = 0;
-    let rule = &rules.iter().find(|x| &x.outer_bag == bag).unwrap();
-    for (count, bag) in rule.inner_bags.iter() {
-        ret += count;
-        ret += get_required_bags(rules, bag) * count;
-    }
+// every bag that can eventually contain the given one, sorted
+fn get_valid_bags<'a>(graph: &'a BagGraph, bag: &Bag) -> Vec<&'a Bag> {
+    let mut ret = match graph.id(bag) {
+        Some(id) => graph.containers(id).into_iter().map(|id| graph.bag(id)).collect(),
+        None => Vec::new(),
+    };
+    ret.sort();
     ret
 }
 
+fn get_required_bags(graph: &BagGraph, bag: &Bag) -> Result<u128, String> {
+    let id = graph.id(bag).ok_or(format!("No rule mentions {}.", bag))?;
+    graph.required_bags(id)
+}
+
 fn main() {
-    let rules = get_rules_from_file("input.txt");
+    let args: Vec<String> = env::args().collect();
+    let report = args.iter().any(|a| a == "--validate");
+    let breakdown = args.iter().any(|a| a == "--breakdown");
+    let dot = args.iter().any(|a| a == "--dot");
+    let mermaid = args.iter().any(|a| a == "--mermaid");
+    // the argument following one of these flags is its value, not the input file
+    let value = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1));
+    let input = args.iter().enumerate().skip(1)
+        .find(|&(i, a)| !a.starts_with("--") && !["--order", "--from", "--to"].contains(&args[i - 1].as_str()))
+        .map(|(_, a)| a.as_str()).unwrap_or("input.txt");
+
+    let rules = if input.ends_with(".json") { get_rules_from_json(input) } else { get_rules_from_file(input) };
+    let mut rules = rules.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // --normalize and --export-json write the rules back out instead of answering questions
+    let export_json = args.iter().any(|a| a == "--export-json");
+    if args.iter().any(|a| a == "--normalize") {
+        rules = normalize(rules).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        if !export_json {
+            for rule in rules.iter() {
+                println!("{}", rule);
+            }
+            return;
+        }
+    }
+    if export_json {
+        println!("{}", serde_json::to_string_pretty(&rules).unwrap());
+        return;
+    }
+
+    let graph = BagGraph::from_rules(&rules);
+
+    // --from <bag> and --to <bag> limit the export to the bags inside or around that bag,
+    // exporting works for any rule set, even an invalid one
+    if dot || mermaid {
+        let lookup = |name: &String| parse_bag_name(name)
+            .and_then(|bag| graph.id(&bag).ok_or(format!("No rule mentions {}.", bag)))
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+        let scope = if let Some(name) = value("--from") {
+            Scope::From(lookup(name))
+        } else if let Some(name) = value("--to") {
+            Scope::To(lookup(name))
+        } else {
+            Scope::All
+        };
+        print!("{}", if dot { to_dot(&graph, scope) } else { to_mermaid(&graph, scope) });
+        return;
+    }
+
+    if args.iter().any(|a| a == "--shell") {
+        let stdin = std::io::stdin();
+        Shell::new(&graph).run(stdin.lock(), std::io::stdout()).unwrap();
+        return;
+    }
+    // --order "3 shiny gold + 2 dark red" lists everything needed for the order, which works for any
+    // rule set, cycles inside the order are reported by Bom
+    if let Some(order) = value("--order") {
+        match Order::from_str(order).and_then(|order| Bom::new(&graph, &order)) {
+            Ok(bom) => print!("{}", bom),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
+
     let target_bag = Bag {color: String::from("gold"), modifier: String::from("shiny")};
-    let valid_bags = get_valid_bags(&rules, &target_bag);
+    let target = graph.id(&target_bag);
+    let validation = validate(&graph, target);
+    if report {
+        print!("{}", validation);
+    }
+    if !validation.is_valid() {
+        if !report {
+            eprint!("{}", validation);
+        }
+        eprintln!("Rules in {} are invalid.", input);
+        std::process::exit(1);
+    }
+    let target = match target {
+        Some(target) => target,
+        // validating needs no target bag, but the answers do
+        None if report => return,
+        None => {
+            eprintln!("No rule mentions {}.", target_bag);
+            std::process::exit(1);
+        },
+    };
+
+    let valid_bags = get_valid_bags(&graph, &target_bag);
     println!("Answer for part 1: {}", valid_bags.len());
-    let c = get_required_bags(&rules, &target_bag);
+    let c = get_required_bags(&graph, &target_bag).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
     println!("Answer for part 2: {}", c);
+    if breakdown {
+        let breakdown = graph.breakdown(target).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        for (id, count) in breakdown {
+            println!("{:>8} {}", count, graph.bag(id));
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 use std::str::FromStr;
-use crate::{Bag, Rule, get_rules_from_file, get_valid_bags, get_required_bags};
+use crate::{Bag, Rule, get_rules_from_file, get_valid_bags, get_required_bags, normalize};
+use crate::testing;
+use crate::graph::BagGraph;
     #[test]
     fn single_bag() {
         let bag = Bag::from_str("light red bags").unwrap();
@@ -168,35 +298,89 @@ use crate::{Bag, Rule, get_rules_from_file, get_valid_bags, get_required_bags};
 
     #[test]
     fn multiple_rules() {
-        let rules = get_rules_from_file("testinput.txt");
+        let rules = get_rules_from_file("testinput.txt").unwrap();
         assert_eq!(rules.len(), 9);
     }
 
     #[test]
     fn can_contain() {
         let rule = Rule::from_str("faded blue bags contain no other bags.").unwrap();
-        assert_eq!(rule.can_contain(&Bag {color: String::from("red"), modifier: String::from("light")}), false);
+        assert!(!rule.can_contain(&Bag {color: String::from("red"), modifier: String::from("light")}));
         let rule = Rule::from_str("bright white bags contain 1 shiny gold bag.").unwrap();
-        assert_eq!(rule.can_contain(&Bag {color: String::from("red"), modifier: String::from("light")}), false);
-        assert_eq!(rule.can_contain(&Bag {color: String::from("gold"), modifier: String::from("shiny")}), true);
+        assert!(!rule.can_contain(&Bag {color: String::from("red"), modifier: String::from("light")}));
+        assert!(rule.can_contain(&Bag {color: String::from("gold"), modifier: String::from("shiny")}));
         let _ = Rule::from_str("light red bags contain 1 bright white bag, 2 muted yellow bags.").unwrap();
     }
 
     #[test]
     fn stacks() {
-        let rules = get_rules_from_file("testinput.txt");
+        let rules = get_rules_from_file("testinput.txt").unwrap();
         assert_eq!(rules.len(), 9);
         let test_bag = Bag {color: String::from("gold"), modifier: String::from("shiny")};
-        let valid_bags = get_valid_bags(&rules, &test_bag);
+        let graph = BagGraph::from_rules(&rules);
+        let valid_bags = get_valid_bags(&graph, &test_bag);
         assert_eq!(valid_bags.len(), 4);
     }
 
     #[test]
     fn bags() {
-        let rules = get_rules_from_file("testinput.txt");
+        let rules = get_rules_from_file("testinput.txt").unwrap();
         assert_eq!(rules.len(), 9);
         let test_bag = Bag {color: String::from("gold"), modifier: String::from("shiny")};
-        let c = get_required_bags(&rules, &test_bag);
-        assert_eq!(c, 32);
+        let c = get_required_bags(&BagGraph::from_rules(&rules), &test_bag);
+        assert_eq!(c, Ok(32));
+        let unknown = Bag {color: String::from("purple"), modifier: String::from("plaid")};
+        assert!(get_required_bags(&BagGraph::from_rules(&rules), &unknown).is_err());
+        let first = Bag {color: String::from("c"), modifier: String::from("m0")};
+        assert!(get_required_bags(&BagGraph::from_rules(&testing::chain(14, 4000000000)), &first).is_err());
+    }
+
+    #[test]
+    fn display() {
+        let rules = get_rules_from_file("testinput.txt").unwrap();
+        let text = std::fs::read_to_string("testinput.txt").unwrap();
+        for (rule, line) in rules.iter().zip(text.lines()) {
+            assert_eq!(rule.to_string(), line);
+        }
+        let rule = Rule::from_str("very pale red bags contain 1 blue bag, 12 dim tan bags.").unwrap();
+        assert_eq!(rule.to_string(), "very pale red bags contain 1 blue bag, 12 dim tan bags.");
+    }
+
+    #[test]
+    fn json() {
+        let rule = Rule::from_str("light red bags contain 1 bright white bag, 2 muted yellow bags.").unwrap();
+        let json = serde_json::to_value(&rule).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "outer_bag": "light red",
+            "inner_bags": [[1, "bright white"], [2, "muted yellow"]],
+        }));
+        assert_eq!(serde_json::from_value::<Rule>(json).unwrap(), rule);
+        assert!(serde_json::from_str::<Rule>(r#"{"outer_bag": "bags", "inner_bags": []}"#).is_err());
+
+        // names are taken as they are, even when they end in bag
+        let rules = testing::rules(&[
+            "dark handbag bags contain 2 wavy sandbags bags.",
+            "wavy sandbags bags contain no other bags.",
+        ]);
+        let json = serde_json::to_string(&rules).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Rule>>(&json).unwrap(), rules);
+    }
+
+    #[test]
+    fn normalized() {
+        let rules = testing::rules(&[
+            "light red bags contain 2 muted yellow bags, 1 bright white bag, 1 muted yellow bag.",
+            "bright white bags contain no other bags.",
+            "light red bags contain no other bags.",
+            "bright white bags contain no other bags.",
+        ]);
+        let rules = normalize(rules).unwrap().iter().map(|r| r.to_string()).collect::<Vec<String>>();
+        assert_eq!(rules, vec![
+            "light red bags contain 1 bright white bag, 3 muted yellow bags.",
+            "bright white bags contain no other bags.",
+        ]);
+
+        let rules = vec![Rule::from_str("light red bags contain 4294967295 dark red bags, 1 dark red bag.").unwrap()];
+        assert!(normalize(rules).is_err());
     }
 }
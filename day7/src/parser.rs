@@ -0,0 +1,172 @@
+use crate::{Bag, Rule};
+
+// A hand written parser for the rule grammar:
+//   rule  = bag " contain " ("no other bags" | inner (", " inner)*) "."
+//   inner = count " " bag
+//   bag   = word (" " word)* " bag" | " bags"
+// All words of a bag but the last are its modifier, the last one is its color.
+// Errors name the column they occurred at, counted in characters from 1.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {input, pos: 0}
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, String> {
+        let column = self.input[..self.pos].chars().count() + 1;
+        let found = match self.rest().trim_start().split(' ').next() {
+            Some("") | None => String::from("end of input"),
+            Some(word) => format!("\"{}\"", word),
+        };
+        Err(format!("column {}: expected {}, found {}", column, expected, found))
+    }
+
+    fn accept(&mut self, literal: &str) -> bool {
+        if self.rest().starts_with(literal) {
+            self.pos += literal.len();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), String> {
+        if !self.accept(literal) {
+            return self.error(&format!("\"{}\"", literal.trim()));
+        }
+        Ok(())
+    }
+
+    fn end(&self) -> Result<(), String> {
+        if !self.rest().is_empty() {
+            return self.error("end of input");
+        }
+        Ok(())
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        let len = self.rest().find([' ', ',', '.']).unwrap_or(self.rest().len());
+        if len == 0 {
+            return None;
+        }
+        let word = &self.rest()[..len];
+        self.pos += len;
+        Some(word)
+    }
+
+    fn count(&mut self) -> Result<u32, String> {
+        let len = self.rest().find(|c: char| !c.is_ascii_digit()).unwrap_or(self.rest().len());
+        if len == 0 {
+            return self.error("a count");
+        }
+        let count = self.rest()[..len].parse::<u32>().or_else(|_| self.error("a count that fits into 32 bits"))?;
+        self.pos += len;
+        Ok(count)
+    }
+
+    fn bag(&mut self) -> Result<Bag, String> {
+        let mut words = Vec::<&str>::new();
+        loop {
+            let start = self.pos;
+            match self.word() {
+                Some("bag") | Some("bags") if !words.is_empty() => break,
+                Some("bag") | Some("bags") => {
+                    self.pos = start;
+                    return self.error("a bag name");
+                }
+                Some(word) => words.push(word),
+                None => return self.error(if words.is_empty() { "a bag name" } else { "a word or \"bags\"" }),
+            }
+            if !self.accept(" ") {
+                self.pos = start + words.last().unwrap().len();
+                return self.error("\" bags\"");
+            }
+        }
+        let color = words.pop().unwrap().to_string();
+        Ok(Bag {color, modifier: words.join(" ")})
+    }
+
+    fn rule(&mut self) -> Result<Rule, String> {
+        let outer_bag = self.bag()?;
+        self.expect(" contain ")?;
+        let mut inner_bags = Vec::<(u32, Bag)>::new();
+        if !self.accept("no other bags") {
+            loop {
+                let count = self.count()?;
+                self.expect(" ")?;
+                inner_bags.push((count, self.bag()?));
+                if !self.accept(", ") {
+                    break;
+                }
+            }
+        }
+        self.expect(".")?;
+        self.end()?;
+        Ok(Rule {outer_bag, inner_bags})
+    }
+}
+
+pub fn parse_bag(input: &str) -> Result<Bag, String> {
+    let mut parser = Parser::new(input.trim());
+    let bag = parser.bag()?;
+    parser.end()?;
+    Ok(bag)
+}
+
+// A bag as typed by a person, the trailing "bag" or "bags" may be left out.
+pub fn parse_bag_name(input: &str) -> Result<Bag, String> {
+    let mut words = input.split_whitespace().collect::<Vec<&str>>();
+    if let Some(&"bag") | Some(&"bags") = words.last() {
+        words.pop();
+    }
+    parse_bag(&format!("{} bags", words.join(" ")))
+}
+
+pub fn parse_rule(input: &str) -> Result<Rule, String> {
+    Parser::new(input.trim_end()).rule()
+}
+
+#[cfg(test)]
+mod tests {
+use crate::Bag;
+use crate::parser::{parse_bag, parse_bag_name, parse_rule};
+    #[test]
+    fn names() {
+        assert_eq!(parse_bag("very pale shiny gold bags"), Ok(Bag {modifier: String::from("very pale shiny"), color: String::from("gold")}));
+        assert_eq!(parse_bag("red bag"), Ok(Bag {modifier: String::new(), color: String::from("red")}));
+        assert_eq!(parse_bag("bags"), Err(String::from("column 1: expected a bag name, found \"bags\"")));
+        assert_eq!(parse_bag_name(" shiny gold "), parse_bag("shiny gold bag"));
+        assert_eq!(parse_bag_name("shiny gold bags"), parse_bag("shiny gold bag"));
+        assert_eq!(parse_bag_name("dark handbag"), Ok(Bag {modifier: String::from("dark"), color: String::from("handbag")}));
+        assert_eq!(parse_bag_name("dark handbag bags"), parse_bag_name("dark handbag"));
+    }
+
+    #[test]
+    fn counts() {
+        let rule = parse_rule("dim tan bags contain 12 red bags, 1000 very dark green bags.\r").unwrap();
+        assert_eq!(rule.inner_bags, vec![
+            (12, Bag {modifier: String::new(), color: String::from("red")}),
+            (1000, Bag {modifier: String::from("very dark"), color: String::from("green")}),
+        ]);
+        assert!(parse_rule("dim tan bags contain 99999999999 red bags.").unwrap_err().contains("32 bits"));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |rule| parse_rule(rule).unwrap_err();
+        assert_eq!(error("dim tan bags contains 1 red bag."), "column 13: expected \"contain\", found \"contains\"");
+        assert_eq!(error("dim tan bags contain red bags."), "column 22: expected a count, found \"red\"");
+        assert_eq!(error("dim tan bags contain 1 red bag"), "column 31: expected \".\", found end of input");
+        assert_eq!(error("dim tan bags contain 1 red bag, no other bags."), "column 33: expected a count, found \"no\"");
+        assert_eq!(error("dim tan bags contain no other bags. extra"), "column 36: expected end of input, found \"extra\"");
+        assert_eq!(error("dim tan, bags contain no other bags."), "column 8: expected \" bags\", found \",\"");
+        assert_eq!(error("dim tan bags contain 1 red"), "column 27: expected \" bags\", found end of input");
+    }
+}
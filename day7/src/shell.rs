@@ -0,0 +1,146 @@
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+use crate::bom::{Bom, Order};
+use crate::graph::{BagGraph, BagId};
+use crate::parser::parse_bag_name;
+
+const HELP: &str = "\
+containers <bag>       bags that can eventually contain <bag>
+required <bag>         number of bags inside <bag>
+path <bag> -> <bag>    how to pack the second bag inside the first
+deepest [<bag>]        longest chain of nested bags, from <bag> or from any bag
+order <n> <bag> + ...  every bag needed for an order and how to pack them
+help                   this text
+quit                   leave the shell";
+
+// Answers queries about a loaded rule set, one line at a time.
+pub struct Shell<'a> {
+    graph: &'a BagGraph,
+}
+
+impl<'a> Shell<'a> {
+    pub fn new(graph: &'a BagGraph) -> Self {
+        Shell {graph}
+    }
+
+    fn bag(&self, name: &str) -> Result<BagId, String> {
+        let bag = parse_bag_name(name)?;
+        self.graph.id(&bag).ok_or(format!("No rule mentions {}.", bag))
+    }
+
+    fn names(&self, ids: &[BagId]) -> Vec<String> {
+        ids.iter().map(|&id| self.graph.bag(id).to_string()).collect()
+    }
+
+    // The answer to a single query, None if the shell should stop.
+    pub fn execute(&self, line: &str) -> Option<Result<String, String>> {
+        let line = line.trim();
+        let (command, argument) = match line.find(' ') {
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+            None => (line, ""),
+        };
+        Some(match command {
+            "" => Ok(String::new()),
+            "quit" | "exit" => return None,
+            "help" => Ok(String::from(HELP)),
+            "containers" => self.bag(argument).map(|id| {
+                let mut bags = self.graph.containers(id);
+                bags.sort_by(|&a, &b| self.graph.bag(a).cmp(self.graph.bag(b)));
+                format!("{} bags can contain {}: {}", bags.len(), self.graph.bag(id), self.names(&bags).join(", "))
+            }),
+            "required" => self.bag(argument).and_then(|id| {
+                self.graph.required_bags(id).map(|count| format!("{} bags inside {}", count, self.graph.bag(id)))
+            }),
+            "path" => self.path(argument),
+            "deepest" => self.deepest(argument),
+            "order" => Order::from_str(argument).and_then(|order| Bom::new(self.graph, &order))
+                .map(|bom| bom.to_string().trim_end().to_string()),
+            _ => Err(format!("Unknown command {}, try help.", command)),
+        })
+    }
+
+    fn path(&self, argument: &str) -> Result<String, String> {
+        let i = argument.find("->").ok_or("Expected path <bag> -> <bag>.")?;
+        let from = self.bag(&argument[..i])?;
+        let to = self.bag(&argument[i + 2..])?;
+        let path = self.graph.path(from, to).ok_or(format!("{} cannot be inside {}.", self.graph.bag(to), self.graph.bag(from)))?;
+        let mut ret = self.graph.bag(from).to_string();
+        let mut total = 1u128;
+        for &(id, count) in path.iter() {
+            ret += &format!(" -> {} {}", count, self.graph.bag(id));
+            total = total.checked_mul(count as u128)
+                .ok_or_else(|| format!("Number of {} bags inside {} along this path does not fit into 128 bits.", self.graph.bag(to), self.graph.bag(from)))?;
+        }
+        Ok(format!("{} ({} along this path)", ret, total))
+    }
+
+    fn deepest(&self, argument: &str) -> Result<String, String> {
+        let starts = if argument.is_empty() {
+            (0..self.graph.len()).filter(|&id| self.graph.outer(id).is_empty()).collect()
+        } else {
+            vec![self.bag(argument)?]
+        };
+        let mut deepest = Vec::new();
+        for id in starts {
+            let chain = self.graph.deepest_chain(id)?;
+            if chain.len() > deepest.len() {
+                deepest = chain;
+            }
+        }
+        Ok(format!("{} levels: {}", deepest.len(), self.names(&deepest).join(" -> ")))
+    }
+
+    pub fn run<R: BufRead, W: Write>(&self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            match self.execute(&line?) {
+                None => break,
+                Some(Ok(answer)) if answer.is_empty() => (),
+                Some(Ok(answer)) => writeln!(output, "{}", answer)?,
+                Some(Err(e)) => writeln!(output, "error: {}", e)?,
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+use crate::get_rules_from_file;
+use crate::graph::BagGraph;
+use crate::shell::Shell;
+use crate::testing::chain;
+    #[test]
+    fn queries() {
+        let graph = BagGraph::from_rules(&get_rules_from_file("testinput.txt").unwrap());
+        let shell = Shell::new(&graph);
+        let run = |line: &str| shell.execute(line).unwrap();
+        assert_eq!(run("containers shiny gold"),
+            Ok(String::from("4 bags can contain shiny gold: dark orange, light red, bright white, muted yellow")));
+        assert_eq!(run("required shiny gold bags"), Ok(String::from("32 bags inside shiny gold")));
+        assert_eq!(run("path light red -> shiny gold"), Ok(String::from("light red -> 1 bright white -> 1 shiny gold (1 along this path)")));
+        assert_eq!(run("deepest shiny gold"), Ok(String::from("3 levels: shiny gold -> dark olive -> faded blue")));
+        assert_eq!(run("deepest"), Ok(String::from("5 levels: light red -> bright white -> shiny gold -> dark olive -> faded blue")));
+        assert!(run("order 2 dark olive").unwrap().starts_with("16 bags, nested 2 levels deep\n"));
+        assert!(run("path shiny gold -> light red").is_err());
+
+        let graph = BagGraph::from_rules(&chain(5, 4000000000));
+        let shell = Shell::new(&graph);
+        assert_eq!(shell.execute("path m0 c -> m4 c").unwrap().map(|answer| answer.ends_with("(256000000000000000000000000000000000000 along this path)")), Ok(true));
+        assert!(shell.execute("path m0 c -> m5 c").unwrap().unwrap_err().contains("128 bits"));
+        assert!(run("required plaid purple").is_err());
+        assert!(run("jump").is_err());
+        assert_eq!(shell.execute("quit"), None);
+    }
+
+    #[test]
+    fn session() {
+        let graph = BagGraph::from_rules(&get_rules_from_file("testinput.txt").unwrap());
+        let mut output = Vec::new();
+        Shell::new(&graph).run("required faded blue\nfoo\nquit\nrequired faded blue\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "> 0 bags inside faded blue\n> error: Unknown command foo, try help.\n> \n");
+    }
+}
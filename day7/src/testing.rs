@@ -0,0 +1,23 @@
+// Fixtures shared by the tests of all modules.
+use std::str::FromStr;
+use crate::{Bag, Rule};
+use crate::graph::{BagGraph, BagId};
+use crate::parser::parse_bag_name;
+
+pub fn rules(rules: &[&str]) -> Vec<Rule> {
+    rules.iter().map(|r| Rule::from_str(r).unwrap()).collect()
+}
+
+pub fn graph(rules: &[&str]) -> BagGraph {
+    BagGraph::from_rules(&self::rules(rules))
+}
+
+pub fn id(graph: &BagGraph, name: &str) -> BagId {
+    graph.id(&parse_bag_name(name).unwrap()).unwrap()
+}
+
+// levels rules nesting bag m0 c into m1 c and so on, each holding count bags of the next
+pub fn chain(levels: usize, count: u32) -> Vec<Rule> {
+    let bag = |i: usize| Bag {modifier: format!("m{}", i), color: String::from("c")};
+    (0..levels).map(|i| Rule {outer_bag: bag(i), inner_bags: vec![(count, bag(i + 1))]}).collect()
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use crate::graph::{BagGraph, BagId};
+
+// Something wrong with a rule set. Cycles, undefined and duplicate bags are errors,
+// unreachable bags are only reported.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Issue {
+    // the bags along the cycle, starting and ending with the same bag
+    Cycle(Vec<BagId>),
+    Undefined(BagId),
+    Duplicate(BagId),
+    Unreachable(BagId),
+}
+
+impl Issue {
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Issue::Unreachable(_))
+    }
+
+    pub fn describe(&self, graph: &BagGraph) -> String {
+        match self {
+            Issue::Cycle(path) => format!("cycle: {}", path.iter().map(|&id| graph.bag(id).to_string()).collect::<Vec<String>>().join(" -> ")),
+            Issue::Undefined(id) => format!("{} is contained in other bags, but has no rule", graph.bag(*id)),
+            Issue::Duplicate(id) => format!("{} has more than one rule, only the first is used", graph.bag(*id)),
+            Issue::Unreachable(id) => format!("{} neither contains nor is inside the target bag", graph.bag(*id)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Validation<'a> {
+    graph: &'a BagGraph,
+    pub issues: Vec<Issue>,
+}
+
+impl Validation<'_> {
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(Issue::is_error)
+    }
+}
+
+impl fmt::Display for Validation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for issue in self.issues.iter() {
+            writeln!(f, "{}: {}", if issue.is_error() { "error" } else { "warning" }, issue.describe(self.graph))?;
+        }
+        Ok(())
+    }
+}
+
+// Checks the whole graph. Unreachable bags are relative to the bag queries are run for, so they
+// are only looked for if there is one.
+pub fn validate(graph: &BagGraph, target: Option<BagId>) -> Validation<'_> {
+    let mut issues = find_cycles(graph).into_iter().map(Issue::Cycle).collect::<Vec<Issue>>();
+    issues.extend((0..graph.len()).filter(|&id| !graph.is_defined(id)).map(Issue::Undefined));
+    issues.extend(graph.duplicates().iter().map(|&id| Issue::Duplicate(id)));
+
+    if let Some(target) = target {
+        let mut connected = vec![false; graph.len()];
+        connected[target] = true;
+        for id in graph.containers(target).into_iter().chain(graph.contents(target)) {
+            connected[id] = true;
+        }
+        issues.extend((0..graph.len()).filter(|&id| !connected[id]).map(Issue::Unreachable));
+    }
+    Validation {graph, issues}
+}
+
+// One cycle for every edge leading back into the current path of a depth first search. If the rules
+// have any cycle at least one is found, but cycles overlapping a found one may not be listed.
+fn find_cycles(graph: &BagGraph) -> Vec<Vec<BagId>> {
+    let mut cycles = Vec::new();
+    let mut visited = vec![false; graph.len()];
+    let mut on_path = vec![false; graph.len()];
+    for start in 0..graph.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        on_path[start] = true;
+        // the current path, with the index of the next edge to follow for every bag
+        let mut path = vec![(start, 0)];
+        while let Some(&mut (current, ref mut edge)) = path.last_mut() {
+            match graph.inner(current).get(*edge) {
+                Some(&(next, _)) => {
+                    *edge += 1;
+                    if on_path[next] {
+                        let begin = path.iter().position(|&(id, _)| id == next).unwrap();
+                        let mut cycle = path[begin..].iter().map(|&(id, _)| id).collect::<Vec<BagId>>();
+                        cycle.push(next);
+                        cycles.push(cycle);
+                    } else if !visited[next] {
+                        visited[next] = true;
+                        on_path[next] = true;
+                        path.push((next, 0));
+                    }
+                }
+                None => {
+                    on_path[current] = false;
+                    path.pop();
+                }
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+use crate::testing::{graph, id};
+use crate::validation::{Issue, validate};
+
+    #[test]
+    fn valid() {
+        let graph = graph(&[
+            "light red bags contain 1 shiny gold bag.",
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain no other bags.",
+        ]);
+        let validation = validate(&graph, Some(id(&graph, "shiny gold bag")));
+        assert!(validation.is_valid());
+        assert_eq!(validation.issues, vec![]);
+    }
+
+    #[test]
+    fn issues() {
+        let graph = graph(&[
+            "light red bags contain 1 shiny gold bag, 1 dark red bag.",
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain 1 light red bag, 3 faded blue bags.",
+            "shiny gold bags contain no other bags.",
+            "dotted black bags contain no other bags.",
+        ]);
+        let (red, gold, dark, blue, black) = (id(&graph, "light red bag"), id(&graph, "shiny gold bag"),
+            id(&graph, "dark red bag"), id(&graph, "faded blue bag"), id(&graph, "dotted black bag"));
+        let validation = validate(&graph, Some(gold));
+        assert!(!validation.is_valid());
+        assert_eq!(validation.issues, vec![
+            Issue::Cycle(vec![red, gold, dark, red]),
+            Issue::Undefined(blue),
+            Issue::Duplicate(gold),
+            Issue::Unreachable(black),
+        ]);
+        assert_eq!(validation.issues[0].describe(&graph), "cycle: light red -> shiny gold -> dark red -> light red");
+
+        // without a bag to run queries for, everything but the unreachable bags is still checked
+        assert_eq!(validate(&graph, None).issues, vec![
+            Issue::Cycle(vec![red, gold, dark, red]),
+            Issue::Undefined(blue),
+            Issue::Duplicate(gold),
+        ]);
+    }
+
+    #[test]
+    fn self_contained() {
+        let graph = graph(&["shiny gold bags contain 1 shiny gold bag."]);
+        let validation = validate(&graph, Some(0));
+        assert_eq!(validation.issues, vec![Issue::Cycle(vec![0, 0])]);
+        assert_eq!(validation.to_string(), "error: cycle: shiny gold -> shiny gold\n");
+    }
+}